@@ -0,0 +1,39 @@
+//! Checks compiled contracts against the Ewasm Contract Interface, printing every violation.
+//!
+//! Usage: `ewasm-check <contract.wasm>...`
+
+use std::{env, fs, process};
+
+use ewasm_api::checker;
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: ewasm-check <contract.wasm>...");
+        process::exit(2);
+    }
+
+    let mut failed = false;
+    for path in &paths {
+        let result = fs::read(path)
+            .map_err(|error| error.to_string())
+            .and_then(|code| checker::check(&code).map_err(|error| error.to_string()));
+        match result {
+            Ok(ref violations) if violations.is_empty() => println!("{}: ok", path),
+            Ok(violations) => {
+                failed = true;
+                for violation in violations {
+                    println!("{}: {}", path, violation);
+                }
+            }
+            Err(error) => {
+                failed = true;
+                println!("{}: {}", path, error);
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,526 @@
+//! Checking compiled contracts against the
+//! [Ewasm Contract Interface](https://github.com/ewasm/design/blob/master/contract_interface.md).
+//!
+//! A contract is only accepted by a VM if it imports nothing but the `ethereum` functions with
+//! their exact signatures, exports exactly `main` and `memory`, has no start function and uses no
+//! floating-point types or instructions. In particular, imports left over by the `debug` feature,
+//! and imports not yet renamed from `env.ethereum_*` by post-processing, are rejected.
+//!
+//! # Examples
+//! ```ignore
+//! use ewasm_api::checker;
+//!
+//! let code = std::fs::read("contract.wasm").unwrap();
+//! for violation in checker::check(&code).unwrap() {
+//!     println!("{}", violation);
+//! }
+//! ```
+
+use std::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+use wasmparser::{
+    BinaryReaderError, CompositeType, ExternalKind, FuncType, Operator, Parser, Payload, TypeRef,
+    ValType,
+};
+
+const I32: ValType = ValType::I32;
+const I64: ValType = ValType::I64;
+
+/// The functions of the `ethereum` namespace, with their parameters and results.
+const ETHEREUM: &[(&str, &[ValType], &[ValType])] = &[
+    ("useGas", &[I64], &[]),
+    ("getGasLeft", &[], &[I64]),
+    ("getAddress", &[I32], &[]),
+    ("getExternalBalance", &[I32, I32], &[]),
+    ("getBlockCoinbase", &[I32], &[]),
+    ("getBlockDifficulty", &[I32], &[]),
+    ("getBlockGasLimit", &[], &[I64]),
+    ("getBlockHash", &[I64, I32], &[I32]),
+    ("getBlockNumber", &[], &[I64]),
+    ("getBlockTimestamp", &[], &[I64]),
+    ("getTxGasPrice", &[I32], &[]),
+    ("getTxOrigin", &[I32], &[]),
+    ("log", &[I32, I32, I32, I32, I32, I32, I32], &[]),
+    ("call", &[I64, I32, I32, I32, I32], &[I32]),
+    ("callCode", &[I64, I32, I32, I32, I32], &[I32]),
+    ("callDelegate", &[I64, I32, I32, I32], &[I32]),
+    ("callStatic", &[I64, I32, I32, I32], &[I32]),
+    ("create", &[I32, I32, I32, I32], &[I32]),
+    ("returnDataCopy", &[I32, I32, I32], &[]),
+    ("getReturnDataSize", &[], &[I32]),
+    ("finish", &[I32, I32], &[]),
+    ("revert", &[I32, I32], &[]),
+    ("callDataCopy", &[I32, I32, I32], &[]),
+    ("getCallDataSize", &[], &[I32]),
+    ("getCaller", &[I32], &[]),
+    ("getCallValue", &[I32], &[]),
+    ("codeCopy", &[I32, I32, I32], &[]),
+    ("getCodeSize", &[], &[I32]),
+    ("externalCodeCopy", &[I32, I32, I32, I32], &[]),
+    ("getExternalCodeSize", &[I32], &[I32]),
+    ("storageLoad", &[I32, I32], &[]),
+    ("storageStore", &[I32, I32], &[]),
+    ("selfDestruct", &[I32], &[]),
+];
+
+/// A way in which a module does not comply with the Ewasm Contract Interface.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// An import other than a function of the `ethereum` namespace.
+    UnknownImport { module: String, name: String },
+    /// An import of the `debug` namespace, which is only available in test VMs.
+    DebugImport { module: String, name: String },
+    /// An `ethereum` function imported with the wrong signature.
+    ImportSignature(String),
+    /// `main` or `memory` is not exported.
+    MissingExport(&'static str),
+    /// An export other than `main` and `memory`.
+    UnexpectedExport(String),
+    /// `main` is not a function without parameters and results, or `memory` is not a memory.
+    InvalidExport(&'static str),
+    /// The module has a start function.
+    StartFunction,
+    /// A floating-point type or instruction, at the given offset in the module.
+    Float(usize),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::UnknownImport { module, name } => {
+                write!(f, "unknown import `{}.{}`", module, name)
+            }
+            Violation::DebugImport { module, name } => write!(
+                f,
+                "debug import `{}.{}`, build without the `debug` feature",
+                module, name
+            ),
+            Violation::ImportSignature(name) => {
+                write!(f, "import `ethereum.{}` has the wrong signature", name)
+            }
+            Violation::MissingExport(name) => write!(f, "missing export `{}`", name),
+            Violation::UnexpectedExport(name) => write!(f, "unexpected export `{}`", name),
+            Violation::InvalidExport("main") => {
+                f.write_str("export `main` is not a function without parameters and results")
+            }
+            Violation::InvalidExport(name) => write!(f, "export `{}` is not a memory", name),
+            Violation::StartFunction => f.write_str("unexpected start function"),
+            Violation::Float(offset) => write!(
+                f,
+                "floating-point type or instruction at offset {:#x}",
+                offset
+            ),
+        }
+    }
+}
+
+/// Returns true if any of the value types is a floating-point type.
+fn has_float(types: &[ValType]) -> bool {
+    types
+        .iter()
+        .any(|ty| matches!(ty, ValType::F32 | ValType::F64))
+}
+
+/// Returns true if the instruction operates on floating-point values, including the conversions
+/// and reinterpretations from and to them.
+fn is_float_instruction(operator: &Operator) -> bool {
+    use Operator::*;
+    matches!(
+        operator,
+        F32Load { .. }
+            | F64Load { .. }
+            | F32Store { .. }
+            | F64Store { .. }
+            | F32Const { .. }
+            | F64Const { .. }
+            | F32Eq
+            | F32Ne
+            | F32Lt
+            | F32Gt
+            | F32Le
+            | F32Ge
+            | F64Eq
+            | F64Ne
+            | F64Lt
+            | F64Gt
+            | F64Le
+            | F64Ge
+            | F32Abs
+            | F32Neg
+            | F32Ceil
+            | F32Floor
+            | F32Trunc
+            | F32Nearest
+            | F32Sqrt
+            | F32Add
+            | F32Sub
+            | F32Mul
+            | F32Div
+            | F32Min
+            | F32Max
+            | F32Copysign
+            | F64Abs
+            | F64Neg
+            | F64Ceil
+            | F64Floor
+            | F64Trunc
+            | F64Nearest
+            | F64Sqrt
+            | F64Add
+            | F64Sub
+            | F64Mul
+            | F64Div
+            | F64Min
+            | F64Max
+            | F64Copysign
+            | I32TruncF32S
+            | I32TruncF32U
+            | I32TruncF64S
+            | I32TruncF64U
+            | I64TruncF32S
+            | I64TruncF32U
+            | I64TruncF64S
+            | I64TruncF64U
+            | F32ConvertI32S
+            | F32ConvertI32U
+            | F32ConvertI64S
+            | F32ConvertI64U
+            | F32DemoteF64
+            | F64ConvertI32S
+            | F64ConvertI32U
+            | F64ConvertI64S
+            | F64ConvertI64U
+            | F64PromoteF32
+            | I32ReinterpretF32
+            | I64ReinterpretF64
+            | F32ReinterpretI32
+            | F64ReinterpretI64
+            | I32TruncSatF32S
+            | I32TruncSatF32U
+            | I32TruncSatF64S
+            | I32TruncSatF64U
+            | I64TruncSatF32S
+            | I64TruncSatF32U
+            | I64TruncSatF64S
+            | I64TruncSatF64U
+            | F32x4ExtractLane { .. }
+            | F32x4ReplaceLane { .. }
+            | F64x2ExtractLane { .. }
+            | F64x2ReplaceLane { .. }
+            | F32x4Splat
+            | F64x2Splat
+            | F32x4Eq
+            | F32x4Ne
+            | F32x4Lt
+            | F32x4Gt
+            | F32x4Le
+            | F32x4Ge
+            | F64x2Eq
+            | F64x2Ne
+            | F64x2Lt
+            | F64x2Gt
+            | F64x2Le
+            | F64x2Ge
+            | F32x4Ceil
+            | F32x4Floor
+            | F32x4Trunc
+            | F32x4Nearest
+            | F32x4Abs
+            | F32x4Neg
+            | F32x4Sqrt
+            | F32x4Add
+            | F32x4Sub
+            | F32x4Mul
+            | F32x4Div
+            | F32x4Min
+            | F32x4Max
+            | F32x4PMin
+            | F32x4PMax
+            | F64x2Ceil
+            | F64x2Floor
+            | F64x2Trunc
+            | F64x2Nearest
+            | F64x2Abs
+            | F64x2Neg
+            | F64x2Sqrt
+            | F64x2Add
+            | F64x2Sub
+            | F64x2Mul
+            | F64x2Div
+            | F64x2Min
+            | F64x2Max
+            | F64x2PMin
+            | F64x2PMax
+            | I32x4TruncSatF32x4S
+            | I32x4TruncSatF32x4U
+            | F32x4ConvertI32x4S
+            | F32x4ConvertI32x4U
+            | I32x4TruncSatF64x2SZero
+            | I32x4TruncSatF64x2UZero
+            | F64x2ConvertLowI32x4S
+            | F64x2ConvertLowI32x4U
+            | F32x4DemoteF64x2Zero
+            | F64x2PromoteLowF32x4
+            | I32x4RelaxedTruncF32x4S
+            | I32x4RelaxedTruncF32x4U
+            | I32x4RelaxedTruncF64x2SZero
+            | I32x4RelaxedTruncF64x2UZero
+            | F32x4RelaxedMadd
+            | F32x4RelaxedNmadd
+            | F64x2RelaxedMadd
+            | F64x2RelaxedNmadd
+            | F32x4RelaxedMin
+            | F32x4RelaxedMax
+            | F64x2RelaxedMin
+            | F64x2RelaxedMax
+    )
+}
+
+/// Checks a compiled contract, returning every violation found. Fails if `code` is not a valid
+/// wasm module.
+pub fn check(code: &[u8]) -> Result<Vec<Violation>, BinaryReaderError> {
+    wasmparser::validate(code)?;
+
+    let mut violations = Vec::new();
+    let mut types: Vec<Option<FuncType>> = Vec::new();
+    // The type of every function, imported functions first.
+    let mut functions: Vec<u32> = Vec::new();
+    let mut exports = Vec::new();
+
+    for payload in Parser::new(0).parse_all(code) {
+        match payload? {
+            Payload::TypeSection(reader) => {
+                for group in reader.into_iter_with_offsets() {
+                    let (offset, group) = group?;
+                    for ty in group.into_types() {
+                        match ty.composite_type {
+                            CompositeType::Func(ty) => {
+                                if has_float(ty.params()) || has_float(ty.results()) {
+                                    violations.push(Violation::Float(offset));
+                                }
+                                types.push(Some(ty));
+                            }
+                            _ => types.push(None),
+                        }
+                    }
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    let (module, name) = (import.module, import.name);
+                    if let TypeRef::Func(index) = import.ty {
+                        functions.push(index);
+                    }
+
+                    let known = ETHEREUM.iter().find(|(known, _, _)| *known == name);
+                    match (module, known, import.ty) {
+                        ("ethereum", Some((_, params, results)), TypeRef::Func(index)) => {
+                            let ty = types.get(index as usize).and_then(Option::as_ref);
+                            if !ty.is_some_and(|ty| {
+                                ty.params() == *params && ty.results() == *results
+                            }) {
+                                violations.push(Violation::ImportSignature(name.into()));
+                            }
+                        }
+                        _ if module == "debug"
+                            || (module == "env" && name.starts_with("debug_")) =>
+                        {
+                            violations.push(Violation::DebugImport {
+                                module: module.into(),
+                                name: name.into(),
+                            });
+                        }
+                        _ => violations.push(Violation::UnknownImport {
+                            module: module.into(),
+                            name: name.into(),
+                        }),
+                    }
+                }
+            }
+            Payload::FunctionSection(reader) => {
+                for function in reader {
+                    functions.push(function?);
+                }
+            }
+            Payload::GlobalSection(reader) => {
+                for global in reader.into_iter_with_offsets() {
+                    let (offset, global) = global?;
+                    if has_float(&[global.ty.content_type]) {
+                        violations.push(Violation::Float(offset));
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    exports.push((export.name.to_string(), export.kind, export.index));
+                }
+            }
+            Payload::StartSection { .. } => violations.push(Violation::StartFunction),
+            Payload::CodeSectionEntry(body) => {
+                let mut locals = body.get_locals_reader()?;
+                for _ in 0..locals.get_count() {
+                    let offset = locals.original_position();
+                    let (_, ty) = locals.read()?;
+                    if has_float(&[ty]) {
+                        violations.push(Violation::Float(offset));
+                    }
+                }
+                for operator in body.get_operators_reader()?.into_iter_with_offsets() {
+                    let (operator, offset) = operator?;
+                    if is_float_instruction(&operator) {
+                        violations.push(Violation::Float(offset));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    for name in &["main", "memory"] {
+        if !exports.iter().any(|(export, _, _)| export == name) {
+            violations.push(Violation::MissingExport(name));
+        }
+    }
+    for (name, kind, index) in exports {
+        match (name.as_str(), kind) {
+            ("main", ExternalKind::Func) => {
+                let ty = functions
+                    .get(index as usize)
+                    .and_then(|ty| types.get(*ty as usize))
+                    .and_then(Option::as_ref);
+                if !ty.is_some_and(|ty| ty.params().is_empty() && ty.results().is_empty()) {
+                    violations.push(Violation::InvalidExport("main"));
+                }
+            }
+            ("main", _) => violations.push(Violation::InvalidExport("main")),
+            ("memory", ExternalKind::Memory) => (),
+            ("memory", _) => violations.push(Violation::InvalidExport("memory")),
+            _ => violations.push(Violation::UnexpectedExport(name)),
+        }
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_wat(source: &str) -> Vec<Violation> {
+        check(&wat::parse_str(source).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn compliant() {
+        let violations = check_wat(
+            r#"
+            (module
+              (import "ethereum" "useGas" (func (param i64)))
+              (import "ethereum" "getBlockHash" (func (param i64 i32) (result i32)))
+              (import "ethereum" "finish" (func (param i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "main")))
+            "#,
+        );
+        assert_eq!(violations, vec![]);
+    }
+
+    #[test]
+    fn imports() {
+        let violations = check_wat(
+            r#"
+            (module
+              (import "ethereum" "useGas" (func (param i32)))
+              (import "ethereum" "unknown" (func))
+              (import "env" "ethereum_finish" (func (param i32 i32)))
+              (import "env" "debug_print32" (func (param i32)))
+              (import "debug" "printMem" (func (param i32 i32)))
+              (import "ethereum" "memory" (memory 1))
+              (memory (export "memory") 1)
+              (func (export "main")))
+            "#,
+        );
+        let unknown = |module: &str, name: &str| Violation::UnknownImport {
+            module: module.into(),
+            name: name.into(),
+        };
+        let debug = |module: &str, name: &str| Violation::DebugImport {
+            module: module.into(),
+            name: name.into(),
+        };
+        assert_eq!(
+            violations,
+            vec![
+                Violation::ImportSignature("useGas".into()),
+                unknown("ethereum", "unknown"),
+                unknown("env", "ethereum_finish"),
+                debug("env", "debug_print32"),
+                debug("debug", "printMem"),
+                unknown("ethereum", "memory"),
+            ]
+        );
+    }
+
+    #[test]
+    fn exports() {
+        assert_eq!(
+            check_wat("(module)"),
+            vec![
+                Violation::MissingExport("main"),
+                Violation::MissingExport("memory"),
+            ]
+        );
+        assert_eq!(
+            check_wat(
+                r#"
+                (module
+                  (memory (export "main") 1)
+                  (func (export "memory") (param i32))
+                  (func (export "other"))
+                  (func $start)
+                  (start $start))
+                "#
+            ),
+            vec![
+                Violation::StartFunction,
+                Violation::InvalidExport("main"),
+                Violation::InvalidExport("memory"),
+                Violation::UnexpectedExport("other".into()),
+            ]
+        );
+        assert_eq!(
+            check_wat(
+                r#"(module (memory (export "memory") 1) (func (export "main") (result i32) i32.const 0))"#
+            ),
+            vec![Violation::InvalidExport("main")]
+        );
+    }
+
+    #[test]
+    fn floats() {
+        let violations = check_wat(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (global f64 (f64.const 0))
+              (func (export "main") (local f32)
+                (drop (i32.trunc_f32_s (f32.const 1.5)))
+                (drop (i64.reinterpret_f64 (f64.promote_f32 (f32.demote_f64 (f64.const 1)))))
+                (drop (i64.add (i64.const 1) (i64.const 2)))))
+            "#,
+        );
+        assert_eq!(violations.len(), 8);
+        assert!(violations
+            .iter()
+            .all(|violation| matches!(violation, Violation::Float(_))));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(check(b"\0asm\x02\0\0\0").is_err());
+    }
+}
@@ -0,0 +1,455 @@
+//! Allocation-free implementations of the Keccak-256 and SHA-256 hash functions.
+//!
+//! # Examples
+//! ```
+//! use ewasm_api::crypto::{keccak256, Keccak256};
+//!
+//! let mut hasher = Keccak256::new();
+//! hasher.update(b"transfer(");
+//! hasher.update(b"address,uint256)");
+//! let hash = hasher.finalize();
+//! assert_eq!(hash, keccak256(b"transfer(address,uint256)"));
+//! assert_eq!(hash.bytes[..4], [0xa9, 0x05, 0x9c, 0xbb]);
+//! ```
+
+use crate::types::Hash;
+
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+const ROTATIONS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+const LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// The number of bytes of Keccak-256 absorbed per permutation.
+const RATE: usize = 136;
+
+/// The Keccak-f[1600] permutation. Usable at compile time, hence the `while` loops.
+const fn keccak_f(state: &mut [u64; 25]) {
+    let mut round = 0;
+    while round < 24 {
+        // Theta.
+        let mut columns = [0u64; 5];
+        let mut x = 0;
+        while x < 5 {
+            columns[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+            x += 1;
+        }
+        let mut x = 0;
+        while x < 5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            let mut y = 0;
+            while y < 5 {
+                state[x + 5 * y] ^= d;
+                y += 1;
+            }
+            x += 1;
+        }
+
+        // Rho and pi.
+        let mut last = state[1];
+        let mut i = 0;
+        while i < 24 {
+            let current = state[LANES[i]];
+            state[LANES[i]] = last.rotate_left(ROTATIONS[i]);
+            last = current;
+            i += 1;
+        }
+
+        // Chi.
+        let mut y = 0;
+        while y < 5 {
+            let row = [
+                state[5 * y],
+                state[5 * y + 1],
+                state[5 * y + 2],
+                state[5 * y + 3],
+                state[5 * y + 4],
+            ];
+            let mut x = 0;
+            while x < 5 {
+                state[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+                x += 1;
+            }
+            y += 1;
+        }
+
+        // Iota.
+        state[0] ^= KECCAK_ROUND_CONSTANTS[round];
+        round += 1;
+    }
+}
+
+/// An incremental Keccak-256 hasher.
+#[derive(Clone)]
+pub struct Keccak256 {
+    state: [u64; 25],
+    buffer: [u8; RATE],
+    buffered: usize,
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Keccak256 {
+            state: [0; 25],
+            buffer: [0; RATE],
+            buffered: 0,
+        }
+    }
+}
+
+impl Keccak256 {
+    /// Creates a hasher of no data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends data to hash.
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let length = data.len().min(RATE - self.buffered);
+            self.buffer[self.buffered..self.buffered + length].copy_from_slice(&data[..length]);
+            self.buffered += length;
+            data = &data[length..];
+            if self.buffered == RATE {
+                self.absorb();
+            }
+        }
+    }
+
+    /// Returns the hash of the data appended so far.
+    pub fn finalize(mut self) -> Hash {
+        for byte in self.buffer[self.buffered..].iter_mut() {
+            *byte = 0;
+        }
+        self.buffer[self.buffered] ^= 0x01;
+        self.buffer[RATE - 1] ^= 0x80;
+        self.absorb();
+
+        let mut ret = Hash::default();
+        for (chunk, lane) in ret.bytes.chunks_mut(8).zip(self.state.iter()) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+        ret
+    }
+
+    /// XORs the buffered block into the state and applies the permutation.
+    fn absorb(&mut self) {
+        for (lane, chunk) in self.state.iter_mut().zip(self.buffer.chunks(8)) {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(chunk);
+            *lane ^= u64::from_le_bytes(bytes);
+        }
+        keccak_f(&mut self.state);
+        self.buffered = 0;
+    }
+}
+
+/// Computes the Keccak-256 hash of `data`, as used for storage slots, event topics and function
+/// selectors.
+pub fn keccak256(data: &[u8]) -> Hash {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Computes the Keccak-256 hash of `data` at compile time. Used to validate the checksum of
+/// address literals.
+pub(crate) const fn const_keccak256(data: &[u8]) -> [u8; 32] {
+    let mut state = [0u64; 25];
+    let mut offset = 0;
+    loop {
+        let length = if data.len() - offset < RATE {
+            data.len() - offset
+        } else {
+            RATE
+        };
+        let mut block = [0u8; RATE];
+        let mut i = 0;
+        while i < length {
+            block[i] = data[offset + i];
+            i += 1;
+        }
+        if length < RATE {
+            block[length] ^= 0x01;
+            block[RATE - 1] ^= 0x80;
+        }
+
+        let mut lane = 0;
+        while lane < RATE / 8 {
+            let mut bytes = [0u8; 8];
+            let mut i = 0;
+            while i < 8 {
+                bytes[i] = block[8 * lane + i];
+                i += 1;
+            }
+            state[lane] ^= u64::from_le_bytes(bytes);
+            lane += 1;
+        }
+        keccak_f(&mut state);
+
+        if length < RATE {
+            break;
+        }
+        offset += RATE;
+    }
+
+    let mut ret = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        ret[i] = state[i / 8].to_le_bytes()[i % 8];
+        i += 1;
+    }
+    ret
+}
+
+const SHA256_ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// The SHA-256 compression function.
+fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (word, chunk) in w.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, word) in SHA256_ROUND_CONSTANTS.iter().zip(w.iter()) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(choice)
+            .wrapping_add(*k)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(majority);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *word = word.wrapping_add(*value);
+    }
+}
+
+/// An incremental SHA-256 hasher.
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    buffer: [u8; 64],
+    buffered: usize,
+    length: u64,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Sha256 {
+            state: SHA256_INITIAL_STATE,
+            buffer: [0; 64],
+            buffered: 0,
+            length: 0,
+        }
+    }
+}
+
+impl Sha256 {
+    /// Creates a hasher of no data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends data to hash.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let length = data.len().min(64 - self.buffered);
+            self.buffer[self.buffered..self.buffered + length].copy_from_slice(&data[..length]);
+            self.buffered += length;
+            data = &data[length..];
+            if self.buffered == 64 {
+                sha256_compress(&mut self.state, &self.buffer);
+                self.buffered = 0;
+            }
+        }
+    }
+
+    /// Returns the hash of the data appended so far.
+    pub fn finalize(mut self) -> Hash {
+        let bits = self.length.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.buffered != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+
+        let mut ret = Hash::default();
+        for (chunk, word) in ret.bytes.chunks_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        ret
+    }
+}
+
+/// Computes the SHA-256 hash of `data`.
+pub fn sha256(data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{const_keccak256, keccak256, sha256, Keccak256, Sha256};
+
+    #[test]
+    fn keccak256_known_hashes() {
+        assert_eq!(
+            keccak256(b"").to_string(),
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            keccak256(b"abc").to_string(),
+            "0x4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        assert_eq!(
+            keccak256(b"Transfer(address,address,uint256)").to_string(),
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+    }
+
+    #[test]
+    fn keccak256_block_boundaries() {
+        assert_eq!(
+            keccak256(&[0; 135]).to_string(),
+            "0x29e3704feeca7fb9ba229f0fa04d9b36449cf3ad6e1d85d9cfff3a10df9abc3e"
+        );
+        assert_eq!(
+            keccak256(&[0; 136]).to_string(),
+            "0x3a5912a7c5faa06ee4fe906253e339467a9ce87d533c65be3c15cb231cdb25f9"
+        );
+        assert_eq!(
+            keccak256(&[0; 137]).to_string(),
+            "0xbee7fbb405cb0d91a8775e338c4a5e4b5d6b2d051f687fa942043cffdc73bd28"
+        );
+        assert_eq!(
+            keccak256(&[b'a'; 300]).to_string(),
+            "0x5b7e0e47a96f32a88b4f14ca177982790807c40e1a105742ba0fc1babe1ef826"
+        );
+    }
+
+    #[test]
+    fn const_keccak256_matches() {
+        const EMPTY: [u8; 32] = const_keccak256(b"");
+        assert_eq!(EMPTY, keccak256(b"").bytes);
+        for length in [1, 40, 135, 136, 137, 272, 300].iter() {
+            let data: Vec<u8> = (0..*length).map(|i| i as u8).collect();
+            assert_eq!(const_keccak256(&data), keccak256(&data).bytes);
+        }
+    }
+
+    #[test]
+    fn sha256_known_hashes() {
+        assert_eq!(
+            sha256(b"").to_string(),
+            "0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(b"abc").to_string(),
+            "0xba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").to_string(),
+            "0x248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        // The padding fits in the last block, or needs another block.
+        assert_eq!(
+            sha256(&[b'a'; 55]).to_string(),
+            "0x9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318"
+        );
+        assert_eq!(
+            sha256(&[b'a'; 56]).to_string(),
+            "0xb35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a"
+        );
+    }
+
+    #[test]
+    fn incremental() {
+        let data = [b'a'; 1000];
+        for split in [0, 1, 64, 135, 136, 137, 999].iter() {
+            let (head, tail) = data.split_at(*split);
+
+            let mut keccak = Keccak256::new();
+            keccak.update(head);
+            keccak.update(tail);
+            assert_eq!(
+                keccak.finalize().to_string(),
+                "0xb6a4ac1f51884d71f30fa397a5e155de3099e11fc0edef5d08b646e621e19de9"
+            );
+
+            let mut sha = Sha256::new();
+            sha.update(head);
+            sha.update(&[]);
+            sha.update(tail);
+            assert_eq!(
+                sha.finalize().to_string(),
+                "0x41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Converting `EtherValue` amounts of wei from and to larger denominations, such as gwei and
+//! ether.
+//!
+//! # Examples
+//! ```
+//! use ewasm_api::ether;
+//!
+//! let price = ether::parse_gwei("1.5").unwrap();
+//! assert_eq!(price, ether::from_gwei(1) + ether::parse_gwei("0.5").unwrap());
+//! assert_eq!(ether::format_ether(&price).to_string(), "0.0000000015");
+//! ```
+
+use crate::types::{EtherValue, ParseError};
+
+/// The number of decimals of gwei, or 10^9 wei.
+pub const GWEI_DECIMALS: u32 = 9;
+
+/// The number of decimals of ether, or 10^18 wei.
+pub const ETHER_DECIMALS: u32 = 18;
+
+/// Creates a value from a whole number of gwei. Cannot overflow.
+pub fn from_gwei(gwei: u64) -> EtherValue {
+    EtherValue::from(u128::from(gwei) * 10u128.pow(GWEI_DECIMALS))
+}
+
+/// Creates a value from a whole number of ether. Cannot overflow.
+pub fn from_ether(ether: u64) -> EtherValue {
+    EtherValue::from(u128::from(ether) * 10u128.pow(ETHER_DECIMALS))
+}
+
+/// Parses a decimal amount of gwei, such as `"1.5"`.
+pub fn parse_gwei(s: &str) -> Result<EtherValue, ParseError> {
+    parse_units(s, GWEI_DECIMALS)
+}
+
+/// Parses a decimal amount of ether, such as `"1.5"`.
+pub fn parse_ether(s: &str) -> Result<EtherValue, ParseError> {
+    parse_units(s, ETHER_DECIMALS)
+}
+
+/// Parses a decimal amount of a unit of 10^`decimals` wei. Fails rather than rounding if the
+/// amount is not a whole number of wei.
+pub fn parse_units(s: &str, decimals: u32) -> Result<EtherValue, ParseError> {
+    let (integer, fraction) = match s.find('.') {
+        Some(point) => (&s[..point], &s[point + 1..]),
+        None => (s, ""),
+    };
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let mut value: u128 = 0;
+    let mut scale = decimals;
+    for (i, c) in integer.bytes().chain(fraction.bytes()).enumerate() {
+        if !c.is_ascii_digit() {
+            return Err(ParseError::InvalidDigit);
+        }
+        if i >= integer.len() {
+            if scale == 0 {
+                if c != b'0' {
+                    return Err(ParseError::TooPrecise);
+                }
+                continue;
+            }
+            scale -= 1;
+        }
+        value = value
+            .checked_mul(10)
+            .and_then(|value| value.checked_add(u128::from(c - b'0')))
+            .ok_or(ParseError::Overflow)?;
+    }
+    if value != 0 {
+        value = 10u128
+            .checked_pow(scale)
+            .and_then(|factor| value.checked_mul(factor))
+            .ok_or(ParseError::Overflow)?;
+    }
+    Ok(EtherValue::from(value))
+}
+
+/// Formats a value in gwei, such as `1.5`.
+pub fn format_gwei(value: &EtherValue) -> Units {
+    format_units(value, GWEI_DECIMALS)
+}
+
+/// Formats a value in ether, such as `1.5`.
+pub fn format_ether(value: &EtherValue) -> Units {
+    format_units(value, ETHER_DECIMALS)
+}
+
+/// Formats a value exactly in a unit of 10^`decimals` wei, without trailing zeros.
+pub fn format_units(value: &EtherValue, decimals: u32) -> Units {
+    Units {
+        wei: u128::from(*value),
+        decimals,
+    }
+}
+
+/// An amount of wei formatted in a larger unit, returned by `format_units`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Units {
+    wei: u128,
+    decimals: u32,
+}
+
+impl core::fmt::Display for Units {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let (integer, mut fraction) = match 10u128.checked_pow(self.decimals) {
+            Some(factor) => (self.wei / factor, self.wei % factor),
+            None => (0, self.wei),
+        };
+        write!(f, "{}", integer)?;
+        if fraction == 0 {
+            return Ok(());
+        }
+        let mut width = self.decimals as usize;
+        while fraction % 10 == 0 {
+            fraction /= 10;
+            width -= 1;
+        }
+        write!(f, ".{:0width$}", fraction, width = width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_ether("1.5"),
+            Ok(EtherValue::from(1_500_000_000_000_000_000u128))
+        );
+        assert_eq!(parse_ether("2"), Ok(from_ether(2)));
+        assert_eq!(parse_ether(".25"), Ok(from_gwei(250_000_000)));
+        assert_eq!(parse_ether("3."), Ok(from_ether(3)));
+        assert_eq!(parse_gwei("0.000000001"), Ok(EtherValue::from(1u8)));
+        assert_eq!(
+            parse_gwei("1.0000000010"),
+            Ok(EtherValue::from(1_000_000_001u64))
+        );
+        assert_eq!(parse_units("42", 0), Ok(EtherValue::from(42u8)));
+        assert_eq!(parse_units("0", 100), Ok(EtherValue::MIN));
+
+        assert_eq!(parse_gwei("0.0000000001"), Err(ParseError::TooPrecise));
+        assert_eq!(
+            parse_ether("340282366920938463464"),
+            Err(ParseError::Overflow)
+        );
+        assert_eq!(parse_units("1", 39), Err(ParseError::Overflow));
+        assert_eq!(parse_ether("."), Err(ParseError::Empty));
+        assert_eq!(parse_ether("1.2.3"), Err(ParseError::InvalidDigit));
+        assert_eq!(parse_ether("-1"), Err(ParseError::InvalidDigit));
+    }
+
+    #[test]
+    fn format() {
+        let value = EtherValue::from(1_500_000_000_000_000_000u128);
+        assert_eq!(format_ether(&value).to_string(), "1.5");
+        assert_eq!(format_gwei(&value).to_string(), "1500000000");
+        assert_eq!(
+            format_ether(&EtherValue::from(1u8)).to_string(),
+            "0.000000000000000001"
+        );
+        assert_eq!(format_ether(&EtherValue::MIN).to_string(), "0");
+        assert_eq!(
+            format_units(&EtherValue::MAX, 0).to_string(),
+            u128::MAX.to_string()
+        );
+        assert_eq!(
+            format_units(&EtherValue::MAX, 40).to_string(),
+            "0.0340282366920938463463374607431768211455"
+        );
+        assert_eq!(
+            parse_ether(&format_ether(&EtherValue::MAX).to_string()),
+            Ok(EtherValue::MAX)
+        );
+    }
+}
@@ -7,11 +7,23 @@
 //! To use ewasm_api, simply include it as a dependency in your project.
 //! ewasm_api can be built with various feature sets:
 //! - `default`: Builds with `wee_alloc` as the global allocator and with the Rust standard
-//! library.
+//!   library.
 //! - `qimalloc`: Builds with [qimalloc](https://github.com/wasmx/qimalloc) as the global
-//! allocator.
+//!   allocator.
 //! - `debug`: Exposes the debugging interface.
 //! - `experimental`: Exposes the experimental bignum system library API.
+//! - `newtypes`: Makes `Address`, `EtherValue`, `StorageKey`, `StorageValue`, `LogTopic` and
+//!   `Hash` distinct types instead of aliases, with explicit conversions between them.
+//! - `primitive-types`: Implements conversions from and to the `U128`, `U256`, `H160` and `H256`
+//!   types of the `primitive-types` and `ethereum-types` crates.
+//! - `serde`: Implements `Serialize` and `Deserialize` for the types in [`types`], as strings in
+//!   the Ethereum JSON-RPC convention.
+//! - `testing`: Implements the EEI natively with an in-memory host, so that contracts can be
+//!   tested with `cargo test`. Has no effect when targeting wasm32.
+//! - `runner`: Exposes an interpreter running compiled contracts against the `testing` host.
+//! - `fixtures`: Runs state tests from JSON fixtures against the `testing` host.
+//! - `checker`: Exposes a checker of compiled contracts against the Ewasm Contract Interface, and
+//!   the `ewasm-check` binary.
 //!
 //! # Examples
 //! ```
@@ -56,6 +68,9 @@ cfg_if! {
 mod native;
 mod utils;
 
+pub mod crypto;
+pub mod ether;
+pub mod precompiles;
 pub mod types;
 
 #[cfg(feature = "debug")]
@@ -70,6 +85,15 @@ pub mod eth2;
 #[cfg(not(feature = "std"))]
 pub mod convert;
 
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
+
+#[cfg(all(feature = "runner", not(target_arch = "wasm32")))]
+pub mod runner;
+
+#[cfg(all(feature = "checker", not(target_arch = "wasm32")))]
+pub mod checker;
+
 #[cfg(feature = "std")]
 use std::vec::Vec;
 
@@ -109,6 +133,191 @@ macro_rules! ewasm_entry_point {
     };
 }
 
+/// Declare entry point for deployment code. Expects the runtime code to deploy, such as
+/// `include_bytes!("runtime.wasm")`, and optionally the name of a constructor function to run
+/// first. The constructor can access the calldata and storage as usual.
+/// This will only compile in when using the wasm32 target.
+///
+/// # Examples
+/// ```ignore
+/// use ewasm_api::prelude::*;
+///
+/// fn constructor() {
+///     storage_store(&StorageKey::default(), &StorageValue::from([1; 32]));
+/// }
+///
+/// ewasm_deployer!(include_bytes!("runtime.wasm"), constructor);
+/// ```
+#[macro_export]
+macro_rules! ewasm_deployer {
+    ($runtime:expr) => {
+        $crate::ewasm_deployer!($runtime, (|| ()));
+    };
+    ($runtime:expr, $constructor:expr) => {
+        #[cfg(target_arch = "wasm32")]
+        #[no_mangle]
+        pub extern "C" fn main() {
+            $crate::__private::deploy($runtime, $constructor)
+        }
+    };
+}
+
+/// Declare an `Address` constant from a hexadecimal literal of 40 digits, optionally prefixed
+/// with `0x`. Invalid literals are rejected at compile time, including mixed-case literals which
+/// do not match their EIP-55 checksum. All-lowercase and all-uppercase literals carry no checksum.
+///
+/// # Examples
+/// ```
+/// use ewasm_api::prelude::*;
+///
+/// const OWNER: Address = address!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+/// assert_eq!(OWNER, address!("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"));
+/// ```
+///
+/// A literal with a missing digit does not compile:
+/// ```compile_fail
+/// use ewasm_api::prelude::*;
+///
+/// const OWNER: Address = address!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAe");
+/// ```
+///
+/// Neither does a literal with a typo in its checksum:
+/// ```compile_fail
+/// use ewasm_api::prelude::*;
+///
+/// const OWNER: Address = address!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD");
+/// ```
+#[macro_export]
+macro_rules! address {
+    ($hex:expr) => {{
+        const ADDRESS: $crate::types::Address = $crate::types::Address {
+            bytes: $crate::types::parse_address_literal($hex),
+        };
+        ADDRESS
+    }};
+}
+
+/// Declare a `Bytes32` constant, such as a `StorageKey` or `Hash`, from a hexadecimal literal of
+/// 64 digits, optionally prefixed with `0x`. Invalid literals are rejected at compile time.
+/// With the `newtypes` feature, use `storage_key!`, `storage_value!`, `log_topic!` or `hash!` to
+/// declare constants of the distinct types.
+///
+/// # Examples
+/// ```
+/// use ewasm_api::prelude::*;
+///
+/// const TRANSFER: Bytes32 =
+///     bytes32!("0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+/// assert_eq!(TRANSFER.bytes[31], 0xef);
+/// ```
+#[macro_export]
+macro_rules! bytes32 {
+    ($hex:expr) => {
+        $crate::__bytes32_literal!(Bytes32, $hex)
+    };
+}
+
+/// Declare a `StorageKey` constant from a hexadecimal literal, as with `bytes32!`.
+///
+/// # Examples
+/// ```
+/// use ewasm_api::prelude::*;
+///
+/// const BALANCES: StorageKey =
+///     storage_key!("0x0000000000000000000000000000000000000000000000000000000000000001");
+/// assert_eq!(BALANCES.bytes[31], 1);
+/// ```
+#[macro_export]
+macro_rules! storage_key {
+    ($hex:expr) => {
+        $crate::__bytes32_literal!(StorageKey, $hex)
+    };
+}
+
+/// Declare a `StorageValue` constant from a hexadecimal literal, as with `bytes32!`.
+#[macro_export]
+macro_rules! storage_value {
+    ($hex:expr) => {
+        $crate::__bytes32_literal!(StorageValue, $hex)
+    };
+}
+
+/// Declare a `LogTopic` constant from a hexadecimal literal, as with `bytes32!`.
+///
+/// # Examples
+/// ```
+/// use ewasm_api::prelude::*;
+///
+/// const TRANSFER: LogTopic =
+///     log_topic!("0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+/// assert_eq!(TRANSFER.bytes[0], 0xdd);
+/// ```
+#[macro_export]
+macro_rules! log_topic {
+    ($hex:expr) => {
+        $crate::__bytes32_literal!(LogTopic, $hex)
+    };
+}
+
+/// Declare a `Hash` constant from a hexadecimal literal, as with `bytes32!`.
+#[macro_export]
+macro_rules! hash {
+    ($hex:expr) => {
+        $crate::__bytes32_literal!(Hash, $hex)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bytes32_literal {
+    ($type:ident, $hex:expr) => {{
+        const VALUE: $crate::types::$type = $crate::types::$type {
+            bytes: $crate::types::parse_hex_literal::<32>($hex),
+        };
+        VALUE
+    }};
+}
+
+/// Declare a `Uint256` constant from a decimal literal, or from a hexadecimal literal prefixed
+/// with `0x`. Invalid or out of range literals are rejected at compile time.
+///
+/// # Examples
+/// ```
+/// use ewasm_api::prelude::*;
+///
+/// const SUPPLY: Uint256 = uint256!("1000000000000000000000000");
+/// assert_eq!(SUPPLY, uint256!("0xd3c21bcecceda1000000"));
+/// ```
+#[macro_export]
+macro_rules! uint256 {
+    ($number:expr) => {{
+        const UINT256: $crate::types::Uint256 = $crate::types::Uint256 {
+            bytes: $crate::types::parse_number_literal($number),
+        };
+        UINT256
+    }};
+}
+
+/// Declare an `EtherValue` constant from a number of wei, as a decimal literal, or a hexadecimal
+/// literal prefixed with `0x`. Invalid or out of range literals are rejected at compile time.
+///
+/// # Examples
+/// ```
+/// use ewasm_api::prelude::*;
+///
+/// const DEPOSIT: EtherValue = ether_value!("32000000000000000000");
+/// assert_eq!(DEPOSIT, ether::from_ether(32));
+/// ```
+#[macro_export]
+macro_rules! ether_value {
+    ($number:expr) => {{
+        const ETHER_VALUE: $crate::types::EtherValue = $crate::types::EtherValue {
+            bytes: $crate::types::parse_wei_literal($number),
+        };
+        ETHER_VALUE
+    }};
+}
+
 /// Enum representing an error code for EEI calls. Currently used by `codeCopy`, `callDataCopy`,
 /// `externalCodeCopy`, and `returnDataCopy`.
 pub enum Error {
@@ -268,10 +477,10 @@ pub fn log0(data: &[u8]) {
     log(
         data,
         0,
-        0 as *const u8,
-        0 as *const u8,
-        0 as *const u8,
-        0 as *const u8,
+        core::ptr::null(),
+        core::ptr::null(),
+        core::ptr::null(),
+        core::ptr::null(),
     )
 }
 
@@ -280,10 +489,10 @@ pub fn log1(data: &[u8], topic1: &LogTopic) {
     log(
         data,
         1,
-        topic1.bytes.as_ptr() as *const u8,
-        0 as *const u8,
-        0 as *const u8,
-        0 as *const u8,
+        topic1.bytes.as_ptr(),
+        core::ptr::null(),
+        core::ptr::null(),
+        core::ptr::null(),
     )
 }
 
@@ -292,10 +501,10 @@ pub fn log2(data: &[u8], topic1: &LogTopic, topic2: &LogTopic) {
     log(
         data,
         2,
-        topic1.bytes.as_ptr() as *const u8,
-        topic2.bytes.as_ptr() as *const u8,
-        0 as *const u8,
-        0 as *const u8,
+        topic1.bytes.as_ptr(),
+        topic2.bytes.as_ptr(),
+        core::ptr::null(),
+        core::ptr::null(),
     )
 }
 
@@ -304,10 +513,10 @@ pub fn log3(data: &[u8], topic1: &LogTopic, topic2: &LogTopic, topic3: &LogTopic
     log(
         data,
         3,
-        topic1.bytes.as_ptr() as *const u8,
-        topic2.bytes.as_ptr() as *const u8,
-        topic3.bytes.as_ptr() as *const u8,
-        0 as *const u8,
+        topic1.bytes.as_ptr(),
+        topic2.bytes.as_ptr(),
+        topic3.bytes.as_ptr(),
+        core::ptr::null(),
     )
 }
 
@@ -322,10 +531,10 @@ pub fn log4(
     log(
         data,
         4,
-        topic1.bytes.as_ptr() as *const u8,
-        topic2.bytes.as_ptr() as *const u8,
-        topic3.bytes.as_ptr() as *const u8,
-        topic4.bytes.as_ptr() as *const u8,
+        topic1.bytes.as_ptr(),
+        topic2.bytes.as_ptr(),
+        topic3.bytes.as_ptr(),
+        topic4.bytes.as_ptr(),
     )
 }
 
@@ -612,7 +821,7 @@ pub fn abort() -> ! {
 /// Halts execution and reverts all changes to the state.
 pub fn revert() -> ! {
     unsafe {
-        native::ethereum_revert(0 as *const u32, 0 as u32);
+        native::ethereum_revert(core::ptr::null(), 0);
     }
 }
 
@@ -626,7 +835,7 @@ pub fn revert_data(data: &[u8]) -> ! {
 /// Ends execution, signalling success.
 pub fn finish() -> ! {
     unsafe {
-        native::ethereum_finish(0 as *const u32, 0 as u32);
+        native::ethereum_finish(core::ptr::null(), 0);
     }
 }
 
@@ -637,6 +846,17 @@ pub fn finish_data(data: &[u8]) -> ! {
     }
 }
 
+/// Functions used by the macros of this crate, which are not part of its API.
+#[doc(hidden)]
+pub mod __private {
+    /// Runs `constructor`, then halts execution returning `runtime` as the code to deploy.
+    /// Used by `ewasm_deployer!`.
+    pub fn deploy<F: FnOnce()>(runtime: &[u8], constructor: F) -> ! {
+        constructor();
+        crate::finish_data(runtime)
+    }
+}
+
 /// Accesses the storage data at the specified key.
 pub fn storage_load(key: &StorageKey) -> StorageValue {
     let mut ret = StorageValue::default();
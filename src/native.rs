@@ -1,6 +1,10 @@
 //! The low-level bindings for the Ethereum Environment Interface (EEI). There is a safe set of wrappers for these functions, so use
 //! those unless you are certain you know what you're doing.
 
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub use crate::testing::eei::*;
+
+#[cfg(not(all(feature = "testing", not(target_arch = "wasm32"))))]
 extern "C" {
     pub fn ethereum_useGas(amount: u64);
     pub fn ethereum_getGasLeft() -> u64;
@@ -0,0 +1,567 @@
+//! Typed wrappers around the precompiled contracts.
+//!
+//! Each wrapper calls its precompile with `call_static`, forwarding exactly the gas the
+//! precompile costs in the Istanbul fork, and decodes the return data.
+//!
+//! `ecrecover`, `sha256`, `ripemd160` and `identity` cannot fail on any input, so a failed call
+//! means that there was not enough gas left to forward, and these wrappers then `abort`,
+//! consuming the remaining gas as the VM would. They also abort if the output is not as long as
+//! the precompile always returns, as on a chain without it. The other precompiles reject invalid
+//! input, and their wrappers return an [`Error`] instead.
+//!
+//! The wrappers which allocate, `identity`, `modexp` and `alt_bn128_pairing`, need `std`.
+//! `identity_into` copies into a given buffer instead.
+
+use super::{abort, call_static, returndata_copy, returndata_size, CallResult};
+use crate::types::*;
+
+#[cfg(feature = "std")]
+pub use self::owned::*;
+
+/// The number of the ecrecover precompile, the last byte of its address.
+pub const ECRECOVER: u8 = 0x01;
+/// The number of the SHA-256 precompile.
+pub const SHA256: u8 = 0x02;
+/// The number of the RIPEMD-160 precompile.
+pub const RIPEMD160: u8 = 0x03;
+/// The number of the identity precompile.
+pub const IDENTITY: u8 = 0x04;
+/// The number of the modexp precompile.
+pub const MODEXP: u8 = 0x05;
+/// The number of the alt_bn128 addition precompile.
+pub const ALT_BN128_ADD: u8 = 0x06;
+/// The number of the alt_bn128 scalar multiplication precompile.
+pub const ALT_BN128_MUL: u8 = 0x07;
+/// The number of the alt_bn128 pairing check precompile.
+pub const ALT_BN128_PAIRING: u8 = 0x08;
+/// The number of the BLAKE2 compression function precompile.
+pub const BLAKE2F: u8 = 0x09;
+
+/// The gas cost of `ecrecover`.
+const ECRECOVER_GAS: u64 = 3000;
+/// The gas cost of `alt_bn128_add`, as of EIP-1108.
+const ALT_BN128_ADD_GAS: u64 = 150;
+/// The gas cost of `alt_bn128_mul`, as of EIP-1108.
+const ALT_BN128_MUL_GAS: u64 = 6000;
+
+/// The error returned by the precompiles which can fail.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The precompile rejected its input, such as a point which is not on the curve, or there
+    /// was not enough gas left to forward to it.
+    Failure,
+    /// The precompile returned output of an unexpected length, as the empty account at its
+    /// address does on a chain without the precompile.
+    InvalidOutput,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Error::Failure => "precompile failed",
+            Error::InvalidOutput => "unexpected precompile output",
+        })
+    }
+}
+
+/// Returns the gas cost of a precompile charging `base` plus `word` for every 32-byte word of
+/// input.
+fn linear_gas(base: u64, word: u64, length: usize) -> u64 {
+    let words = (length as u64).div_ceil(32);
+    base.saturating_add(word.saturating_mul(words))
+}
+
+/// Returns the address of the precompile numbered `index`.
+pub fn precompile_address(index: u8) -> Address {
+    let mut ret = Address::default();
+    ret.bytes[19] = index;
+    ret
+}
+
+/// Calls the precompile numbered `index` with `gas`, returning whether it succeeded.
+pub(crate) fn call_precompile(index: u8, gas: u64, input: &[u8]) -> bool {
+    matches!(
+        call_static(gas, &precompile_address(index), input),
+        CallResult::Successful
+    )
+}
+
+/// Calls a precompile which cannot fail, aborting if it does, and copies as much of its output as
+/// fits into `ret`. Returns the length of the output.
+fn call_infallible(index: u8, gas: u64, input: &[u8], ret: &mut [u8]) -> usize {
+    if !call_precompile(index, gas, input) {
+        abort();
+    }
+    let length = returndata_size();
+    let copied = length.min(ret.len());
+    if returndata_copy(0, copied, ret).is_err() {
+        abort();
+    }
+    length
+}
+
+/// Calls a precompile which cannot fail and always returns as many bytes as `ret` holds, and copies
+/// its output into `ret`, aborting if the call fails or the output has a different length.
+fn call_exact(index: u8, gas: u64, input: &[u8], ret: &mut [u8]) {
+    if call_infallible(index, gas, input, ret) != ret.len() {
+        abort();
+    }
+}
+
+/// Calls a precompile which can fail, and copies its output into `ret`, which must be exactly as
+/// long as the output.
+fn call_fallible(index: u8, gas: u64, input: &[u8], ret: &mut [u8]) -> Result<(), Error> {
+    if !call_precompile(index, gas, input) {
+        return Err(Error::Failure);
+    }
+    if returndata_size() != ret.len() {
+        return Err(Error::InvalidOutput);
+    }
+    returndata_copy(0, ret.len(), ret).map_err(|_| Error::InvalidOutput)
+}
+
+/// Recovers the address which signed `hash`, given the recovery identifier `v` (27 or 28) and
+/// the signature values `r` and `s`. Returns `None` if the signature is invalid.
+pub fn ecrecover(hash: &Hash, v: u8, r: &Bytes32, s: &Bytes32) -> Option<Address> {
+    let mut input = [0u8; 128];
+    input[..32].copy_from_slice(&hash.bytes);
+    input[63] = v;
+    input[64..96].copy_from_slice(&r.bytes);
+    input[96..].copy_from_slice(&s.bytes);
+
+    let mut output = [0u8; 32];
+    match call_infallible(ECRECOVER, ECRECOVER_GAS, &input, &mut output) {
+        32 => {
+            let mut ret = Address::default();
+            ret.bytes.copy_from_slice(&output[12..]);
+            Some(ret)
+        }
+        _ => None,
+    }
+}
+
+/// Computes the SHA-256 hash of `data` with the precompile. See also `crypto::sha256`, which
+/// computes it in the contract.
+pub fn sha256(data: &[u8]) -> Hash {
+    let mut ret = Hash::default();
+    call_exact(SHA256, linear_gas(60, 12, data.len()), data, &mut ret.bytes);
+    ret
+}
+
+/// Computes the RIPEMD-160 hash of `data` with the precompile.
+pub fn ripemd160(data: &[u8]) -> Bytes20 {
+    let mut output = [0u8; 32];
+    call_exact(
+        RIPEMD160,
+        linear_gas(600, 120, data.len()),
+        data,
+        &mut output,
+    );
+    let mut ret = Bytes20::default();
+    ret.bytes.copy_from_slice(&output[12..]);
+    ret
+}
+
+/// Copies `data` into `ret` with the identity precompile, as compilers do to copy memory. Aborts
+/// if `ret` is not as long as `data`.
+pub fn identity_into(data: &[u8], ret: &mut [u8]) {
+    call_exact(IDENTITY, linear_gas(15, 3, data.len()), data, ret);
+}
+
+/// A point on the alt_bn128 curve, in affine coordinates. The point at infinity is `(0, 0)`.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct G1Point {
+    pub x: Uint256,
+    pub y: Uint256,
+}
+
+impl G1Point {
+    /// Encodes the point as the precompiles expect it: the big-endian coordinates `x` and `y`.
+    pub fn encode(&self) -> [u8; 64] {
+        let mut ret = [0u8; 64];
+        ret[..32].copy_from_slice(&self.x.to_be_bytes());
+        ret[32..].copy_from_slice(&self.y.to_be_bytes());
+        ret
+    }
+
+    /// Decodes a point encoded by `encode`.
+    pub fn decode(bytes: &[u8; 64]) -> Self {
+        let (mut x, mut y) = ([0u8; 32], [0u8; 32]);
+        x.copy_from_slice(&bytes[..32]);
+        y.copy_from_slice(&bytes[32..]);
+        G1Point {
+            x: Uint256::from_be_bytes(x),
+            y: Uint256::from_be_bytes(y),
+        }
+    }
+}
+
+/// A point on the twist of the alt_bn128 curve over F_p², in affine coordinates. Each coordinate
+/// `a + b·i` is stored as `[b, a]`, the order in which the precompile and Solidity verifiers
+/// encode it. The point at infinity is all zeros.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct G2Point {
+    pub x: [Uint256; 2],
+    pub y: [Uint256; 2],
+}
+
+impl G2Point {
+    /// Encodes the point as the pairing precompile expects it: the big-endian `x[0]`, `x[1]`,
+    /// `y[0]` and `y[1]`.
+    pub fn encode(&self) -> [u8; 128] {
+        let mut ret = [0u8; 128];
+        let coordinates = [self.x[0], self.x[1], self.y[0], self.y[1]];
+        for (chunk, coordinate) in ret.chunks_mut(32).zip(coordinates.iter()) {
+            chunk.copy_from_slice(&coordinate.to_be_bytes());
+        }
+        ret
+    }
+}
+
+/// Adds two points of the alt_bn128 curve. Fails if either is not on the curve.
+pub fn alt_bn128_add(a: &G1Point, b: &G1Point) -> Result<G1Point, Error> {
+    let mut input = [0u8; 128];
+    input[..64].copy_from_slice(&a.encode());
+    input[64..].copy_from_slice(&b.encode());
+    let mut output = [0u8; 64];
+    call_fallible(ALT_BN128_ADD, ALT_BN128_ADD_GAS, &input, &mut output)?;
+    Ok(G1Point::decode(&output))
+}
+
+/// Multiplies a point of the alt_bn128 curve by a scalar. Fails if the point is not on the curve.
+pub fn alt_bn128_mul(point: &G1Point, scalar: &Uint256) -> Result<G1Point, Error> {
+    let mut input = [0u8; 96];
+    input[..64].copy_from_slice(&point.encode());
+    input[64..].copy_from_slice(&scalar.to_be_bytes());
+    let mut output = [0u8; 64];
+    call_fallible(ALT_BN128_MUL, ALT_BN128_MUL_GAS, &input, &mut output)?;
+    Ok(G1Point::decode(&output))
+}
+
+/// Applies `rounds` rounds of the BLAKE2b compression function F to the state `h`, with the
+/// message block `m`, the offset counters `t` and the final block flag `last`, as of EIP-152.
+/// Returns the new state.
+pub fn blake2f(
+    rounds: u32,
+    h: &[u64; 8],
+    m: &[u64; 16],
+    t: &[u64; 2],
+    last: bool,
+) -> Result<[u64; 8], Error> {
+    let mut input = [0u8; 213];
+    input[..4].copy_from_slice(&rounds.to_be_bytes());
+    let words = h.iter().chain(m.iter()).chain(t.iter());
+    for (chunk, word) in input[4..212].chunks_mut(8).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    input[212] = last as u8;
+
+    let mut output = [0u8; 64];
+    call_fallible(BLAKE2F, u64::from(rounds), &input, &mut output)?;
+    let mut ret = [0u64; 8];
+    for (word, chunk) in ret.iter_mut().zip(output.chunks(8)) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        *word = u64::from_le_bytes(bytes);
+    }
+    Ok(ret)
+}
+
+/// The wrappers which allocate their input or output.
+#[cfg(feature = "std")]
+mod owned {
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// Copies `data` with the identity precompile, as compilers do to copy memory.
+    pub fn identity(data: &[u8]) -> Vec<u8> {
+        let mut ret = vec![0u8; data.len()];
+        identity_into(data, &mut ret);
+        ret
+    }
+
+    /// Returns the number of significant bits of a big-endian number.
+    fn bit_length(bytes: &[u8]) -> u64 {
+        match bytes.iter().position(|byte| *byte != 0) {
+            Some(i) => (bytes.len() - i) as u64 * 8 - u64::from(bytes[i].leading_zeros()),
+            None => 0,
+        }
+    }
+
+    /// Returns the gas cost of `modexp` as of EIP-198.
+    pub(super) fn modexp_gas(base_length: usize, exponent: &[u8], modulus_length: usize) -> u64 {
+        let x = base_length.max(modulus_length) as u128;
+        let complexity = if x <= 64 {
+            x * x
+        } else if x <= 1024 {
+            x * x / 4 + 96 * x - 3072
+        } else {
+            x * x / 16 + 480 * x - 199_680
+        };
+
+        let head = &exponent[..exponent.len().min(32)];
+        let extra = (exponent.len().saturating_sub(32) as u128) * 8;
+        let adjusted_exponent_length = extra + u128::from(bit_length(head).saturating_sub(1));
+
+        let gas = complexity.saturating_mul(adjusted_exponent_length.max(1)) / 20;
+        if gas > u128::from(u64::MAX) {
+            u64::MAX
+        } else {
+            gas as u64
+        }
+    }
+
+    /// Computes `base` to the power of `exponent`, modulo `modulus`. The numbers are big-endian of any
+    /// length, and the result is as long as `modulus`. A modulus of zero yields zero.
+    pub fn modexp(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut input = Vec::with_capacity(96 + base.len() + exponent.len() + modulus.len());
+        for length in [base.len(), exponent.len(), modulus.len()].iter() {
+            input.extend_from_slice(&Uint256::from(*length as u64).to_be_bytes());
+        }
+        input.extend_from_slice(base);
+        input.extend_from_slice(exponent);
+        input.extend_from_slice(modulus);
+
+        let mut ret = vec![0u8; modulus.len()];
+        let gas = modexp_gas(base.len(), exponent, modulus.len());
+        call_fallible(MODEXP, gas, &input, &mut ret)?;
+        Ok(ret)
+    }
+
+    /// The base gas cost of `alt_bn128_pairing`, and its cost per pair, as of EIP-1108.
+    const ALT_BN128_PAIRING_GAS: (u64, u64) = (45000, 34000);
+
+    /// Returns whether the product of the pairings of each pair of points is one, as needed to
+    /// verify zkSNARK proofs. Fails if a point is not on its curve or not in the right subgroup.
+    pub fn alt_bn128_pairing(pairs: &[(G1Point, G2Point)]) -> Result<bool, Error> {
+        let mut input = Vec::with_capacity(192 * pairs.len());
+        for (g1, g2) in pairs.iter() {
+            input.extend_from_slice(&g1.encode());
+            input.extend_from_slice(&g2.encode());
+        }
+        let (base, pair) = ALT_BN128_PAIRING_GAS;
+        let gas = base.saturating_add(pair.saturating_mul(pairs.len() as u64));
+        let mut output = [0u8; 32];
+        call_fallible(ALT_BN128_PAIRING, gas, &input, &mut output)?;
+        Ok(Uint256::from_be_bytes(output) == Uint256::from(1u8))
+    }
+}
+
+#[cfg(all(test, feature = "testing", not(target_arch = "wasm32")))]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::owned::modexp_gas;
+    use super::*;
+    use crate::testing::{execute, set_host, ExecutionOutcome, Host};
+    use crate::{calldata_acquire, finish, finish_data, gas_left, revert};
+
+    /// Registers a mock of the precompile numbered `index`, which records the gas it was given.
+    fn mock<F: Fn(Vec<u8>) + 'static>(host: &mut Host, index: u8, f: F) -> Rc<Cell<u64>> {
+        let gas = Rc::new(Cell::new(0));
+        let recorded = gas.clone();
+        host.register_contract(&precompile_address(index), move || {
+            // Include the cost of `getGasLeft` itself.
+            recorded.set(gas_left() + 2);
+            f(calldata_acquire())
+        });
+        gas
+    }
+
+    #[test]
+    fn precompiles() {
+        let mut host = Host::new();
+        let ecrecover_gas = mock(&mut host, ECRECOVER, |input| {
+            assert_eq!(input.len(), 128);
+            assert!(input[32..63].iter().all(|byte| *byte == 0));
+            if input[63] != 27 {
+                finish();
+            }
+            let mut output = [0u8; 32];
+            output[12..].copy_from_slice(&input[64..84]);
+            finish_data(&output)
+        });
+        let sha256_gas = mock(&mut host, SHA256, |input| {
+            finish_data(&crate::crypto::sha256(&input).bytes)
+        });
+        let ripemd160_gas = mock(&mut host, RIPEMD160, |input| {
+            let mut output = [0u8; 32];
+            output[12..].copy_from_slice(&[input.len() as u8; 20]);
+            finish_data(&output)
+        });
+        let identity_gas = mock(&mut host, IDENTITY, |input| finish_data(&input));
+        set_host(host);
+
+        let execution = execute(|| {
+            let hash = Hash::from([1; 32]);
+            let r = Bytes32::from([0xaa; 32]);
+            let s = Bytes32::from([0xbb; 32]);
+            assert_eq!(
+                ecrecover(&hash, 27, &r, &s),
+                Some(Address::from([0xaa; 20]))
+            );
+            assert_eq!(ecrecover(&hash, 29, &r, &s), None);
+
+            assert_eq!(sha256(b"abc"), crate::crypto::sha256(b"abc"));
+            assert_eq!(ripemd160(&[0; 33]), Bytes20::from([33; 20]));
+            assert_eq!(identity(b"copied"), b"copied");
+            let mut copy = [0u8; 6];
+            identity_into(b"copied", &mut copy);
+            assert_eq!(&copy, b"copied");
+            assert_eq!(identity(&[]), Vec::<u8>::new());
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+
+        assert_eq!(ecrecover_gas.get(), 3000);
+        assert_eq!(sha256_gas.get(), 60 + 12);
+        assert_eq!(ripemd160_gas.get(), 600 + 2 * 120);
+        assert_eq!(identity_gas.get(), 15);
+    }
+
+    fn point(x: u8, y: u8) -> G1Point {
+        G1Point {
+            x: Uint256::from(x),
+            y: Uint256::from(y),
+        }
+    }
+
+    #[test]
+    fn modexp_gas_cost() {
+        // The examples of EIP-198.
+        let mut exponent = [0xff; 32];
+        exponent[27] = 0xfe;
+        exponent[28..].copy_from_slice(&[0xff, 0xff, 0xfc, 0x2e]);
+        assert_eq!(modexp_gas(1, &exponent, 32), 13056);
+        assert_eq!(modexp_gas(0, &exponent, 32), 13056);
+
+        assert_eq!(modexp_gas(1, &[], 1), 0);
+        assert_eq!(modexp_gas(1, &[1], 1), 0);
+        assert_eq!(modexp_gas(64, &[0x80], 64), 64 * 64 * 7 / 20);
+        assert_eq!(
+            modexp_gas(100, &[0; 33], 1),
+            (100 * 100 / 4 + 96 * 100 - 3072) * 8 / 20
+        );
+        assert_eq!(
+            modexp_gas(2000, &[1], 1),
+            (2000 * 2000 / 16 + 480 * 2000 - 199_680) / 20
+        );
+        assert_eq!(modexp_gas(1 << 40, &[0xff; 64], 1), u64::MAX);
+    }
+
+    #[test]
+    fn heavy_precompiles() {
+        let mut host = Host::new();
+        let modexp_gas = mock(&mut host, MODEXP, |input| {
+            let mut lengths = [0u8; 96];
+            lengths[31] = 32;
+            lengths[63] = 1;
+            lengths[95] = 32;
+            assert_eq!(input[..96], lengths[..]);
+            let (base, exponent, modulus) = (input[127] as u32, input[128] as u32, input[160]);
+            let mut output = [0u8; 32];
+            output[31] = (base.pow(exponent) % modulus as u32) as u8;
+            finish_data(&output)
+        });
+        let add_gas = mock(&mut host, ALT_BN128_ADD, |input| {
+            assert_eq!(input.len(), 128);
+            if input[31] == 0xff {
+                revert();
+            }
+            finish_data(&[&input[..32], &input[96..]].concat())
+        });
+        let mul_gas = mock(&mut host, ALT_BN128_MUL, |input| {
+            assert_eq!(input.len(), 96);
+            finish_data(&[&input[64..], &input[32..64]].concat())
+        });
+        let pairing_gas = mock(&mut host, ALT_BN128_PAIRING, |input| {
+            assert_eq!(input.len(), 2 * 192);
+            let mut output = [0u8; 32];
+            output[31] = (input[64 + 31] == 4 && input[192 + 64 + 127] == 7) as u8;
+            finish_data(&output)
+        });
+        let blake2f_gas = mock(&mut host, BLAKE2F, |input| {
+            assert_eq!(input.len(), 213);
+            assert_eq!(input[..4], 1000u32.to_be_bytes());
+            assert_eq!(input[212], 1);
+            // Returns the message block instead of the new state.
+            finish_data(&input[68..132])
+        });
+        set_host(host);
+
+        let execution = execute(|| {
+            let (mut base, mut modulus, mut expected) = ([0u8; 32], [0u8; 32], [0u8; 32]);
+            base[31] = 3;
+            modulus[31] = 7;
+            expected[31] = 81 % 7;
+            assert_eq!(modexp(&base, &[4], &modulus), Ok(expected.to_vec()));
+
+            assert_eq!(alt_bn128_add(&point(1, 2), &point(3, 4)), Ok(point(1, 4)));
+            assert_eq!(
+                alt_bn128_add(&point(0xff, 2), &point(3, 4)),
+                Err(Error::Failure)
+            );
+            assert_eq!(
+                alt_bn128_mul(&point(1, 2), &Uint256::from(5u8)),
+                Ok(point(5, 2))
+            );
+
+            let g2 = |last: u8| G2Point {
+                x: [Uint256::from(4u8), Uint256::default()],
+                y: [Uint256::default(), Uint256::from(last)],
+            };
+            let pairs = [(point(1, 2), g2(0)), (point(1, 2), g2(7))];
+            assert_eq!(alt_bn128_pairing(&pairs), Ok(true));
+            let pairs = [(point(1, 2), g2(0)), (point(1, 2), g2(8))];
+            assert_eq!(alt_bn128_pairing(&pairs), Ok(false));
+
+            let mut m = [0u64; 16];
+            m[0] = 0x0102_0304_0506_0708;
+            m[7] = u64::MAX;
+            let h = [9; 8];
+            let mut expected = [0u64; 8];
+            expected.copy_from_slice(&m[..8]);
+            assert_eq!(blake2f(1000, &h, &m, &[3, 0], true), Ok(expected));
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+
+        assert_eq!(modexp_gas.get(), 32 * 32 * 2 / 20);
+        assert_eq!(add_gas.get(), 150);
+        assert_eq!(mul_gas.get(), 6000);
+        assert_eq!(pairing_gas.get(), 45000 + 2 * 34000);
+        assert_eq!(blake2f_gas.get(), 1000);
+    }
+
+    #[test]
+    fn short_output() {
+        let mut host = Host::new();
+        mock(&mut host, SHA256, |_| finish_data(&[0; 31]));
+        mock(&mut host, IDENTITY, |input| finish_data(&input[1..]));
+        set_host(host);
+
+        let execution = execute(|| {
+            sha256(b"abc");
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Aborted);
+        let execution = execute(|| {
+            identity(b"copied");
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Aborted);
+        let execution = execute(|| identity_into(b"copied", &mut [0; 7]));
+        assert_eq!(execution.outcome, ExecutionOutcome::Aborted);
+    }
+
+    #[test]
+    fn missing_precompile() {
+        set_host(Host::new());
+        let execution = execute(|| {
+            // Without a precompile at its address, the call succeeds without output.
+            assert_eq!(
+                alt_bn128_mul(&point(1, 2), &Uint256::from(5u8)),
+                Err(Error::InvalidOutput)
+            );
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+    }
+}
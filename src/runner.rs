@@ -0,0 +1,1010 @@
+//! Running compiled contracts in an embedded wasm interpreter, against the host of the
+//! [`testing`](crate::testing) module.
+//!
+//! Every function imported by this crate is provided. The `ethereum` functions operate on the
+//! test host exactly like in native tests, so contracts can be tested both natively and compiled,
+//! and compiled contracts can call native ones and vice versa. The `debug` functions print to the
+//! standard output, while the `bignum` and `eth2` functions are implemented in Rust, the latter
+//! operating on the [`Shard`](crate::testing::Shard) of the host.
+//!
+//! Imports are resolved both by their ewasm name, such as `ethereum.useGas`, and by the name
+//! emitted by the compiler before the module is post-processed, such as `env.ethereum_useGas`.
+//!
+//! # Examples
+//! ```ignore
+//! use ewasm_api::prelude::*;
+//! use ewasm_api::{runner, testing};
+//!
+//! let code = include_bytes!("../target/wasm32-unknown-unknown/release/contract.wasm");
+//! let execution = runner::execute(code).unwrap();
+//! assert_eq!(execution.outcome, testing::ExecutionOutcome::Finished(vec![]));
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::vec::Vec;
+
+use wasmi::core::{Trap, TrapCode};
+use wasmi::{Engine, Extern, ExternType, IntoFunc, Linker, Module, Store};
+
+use crate::crypto::keccak256;
+use crate::testing::eei::*;
+use crate::testing::{self, with_host, Execution};
+use crate::types::*;
+
+/// An error loading or instantiating a compiled contract.
+#[derive(Debug)]
+pub enum Error {
+    /// The module could not be decoded or validated.
+    InvalidModule(wasmi::Error),
+    /// The module imports a function which is not provided, or with a different signature.
+    UnknownImport(wasmi::Error),
+    /// The module could not be instantiated, for instance because a memory exceeds its limits or
+    /// a data segment does not fit into its memory.
+    Instantiation(wasmi::Error),
+    /// The start function of the module trapped.
+    StartTrapped(wasmi::Error),
+    /// The module does not export a `main` function without parameters and results.
+    MissingMain,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidModule(error) => write!(f, "invalid module: {}", error),
+            Error::UnknownImport(error) => write!(f, "unknown import: {}", error),
+            Error::Instantiation(error) => write!(f, "instantiation failed: {}", error),
+            Error::StartTrapped(error) => write!(f, "start function trapped: {}", error),
+            Error::MissingMain => f.write_str("missing main function"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The data of the store a contract is instantiated in.
+#[derive(Default)]
+struct State {
+    /// The panic payload of an imported function which halted the contract.
+    halt: Option<Box<dyn Any + Send>>,
+}
+
+type Caller<'a> = wasmi::Caller<'a, State>;
+
+thread_local! {
+    /// The modules compiled on this thread, keyed by the hash of their code.
+    static MODULES: RefCell<BTreeMap<[u8; 32], Rc<Module>>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Executes the compiled contract `code` against the host of the current thread, as the call
+/// described by its `message`. See [`testing::execute`] for details.
+pub fn execute(code: &[u8]) -> Result<Execution, Error> {
+    let module = load(code)?;
+    let mut error = None;
+    let execution = testing::execute(|| {
+        if let Err(invalid) = invoke(&module) {
+            error = Some(invalid);
+            // Abort, rolling back the changes made by the start function.
+            panic::resume_unwind(Box::new(()));
+        }
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(execution),
+    }
+}
+
+/// Runs the compiled contract `code` until it returns or halts.
+pub(crate) fn run(code: &[u8]) {
+    if let Err(error) = load(code).and_then(|module| invoke(&module)) {
+        panic!("{}", error);
+    }
+}
+
+/// Decodes and validates `code`, checking that it exports `main`. Each module is compiled once
+/// per thread, and reused for every call to the same code.
+fn load(code: &[u8]) -> Result<Rc<Module>, Error> {
+    let hash = keccak256(code).bytes;
+    if let Some(module) = MODULES.with(|modules| modules.borrow().get(&hash).cloned()) {
+        return Ok(module);
+    }
+
+    let engine = Engine::default();
+    let module = Module::new(&engine, code).map_err(Error::InvalidModule)?;
+    match module.get_export("main") {
+        Some(ExternType::Func(ty)) if ty.params().is_empty() && ty.results().is_empty() => (),
+        _ => return Err(Error::MissingMain),
+    }
+
+    let module = Rc::new(module);
+    MODULES.with(|modules| modules.borrow_mut().insert(hash, module.clone()));
+    Ok(module)
+}
+
+/// Instantiates `module` and calls its `main` function. If it halts, the thread is unwound with
+/// the reason, and a trap in `main` aborts the contract.
+fn invoke(module: &Module) -> Result<(), Error> {
+    let mut store = Store::new(module.engine(), State::default());
+    let instance = linker(module.engine())
+        .instantiate(&mut store, module)
+        .map_err(|error| match error {
+            wasmi::Error::Linker(_) => Error::UnknownImport(error),
+            _ => Error::Instantiation(error),
+        })?
+        .start(&mut store);
+    if let Some(payload) = store.data_mut().halt.take() {
+        panic::resume_unwind(payload);
+    }
+
+    let result = instance
+        .map_err(Error::StartTrapped)?
+        .get_typed_func::<(), ()>(&store, "main")
+        .expect("main is checked when loading")
+        .call(&mut store, ());
+    if let Some(payload) = store.data_mut().halt.take() {
+        panic::resume_unwind(payload);
+    }
+    if let Err(error) = result {
+        panic!("contract trapped: {}", error);
+    }
+    Ok(())
+}
+
+/// Runs an imported function. If it halts the contract, the reason is kept and a trap is returned
+/// to stop the interpreter.
+fn eei<R, F: FnOnce() -> R>(caller: &mut Caller, f: F) -> Result<R, Trap> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        caller.data_mut().halt = Some(payload);
+        Trap::new("halted")
+    })
+}
+
+/// Returns a pointer to `length` bytes of the contract's memory at `offset`.
+fn memory(caller: &mut Caller, offset: u32, length: u32) -> Result<*const u32, Trap> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("missing memory export"))?;
+    let data = memory.data_mut(caller);
+
+    let (offset, length) = (offset as usize, length as usize);
+    match offset.checked_add(length) {
+        Some(end) if end <= data.len() => Ok(data[offset..].as_mut_ptr() as *const u32),
+        _ => Err(TrapCode::MemoryOutOfBounds.into()),
+    }
+}
+
+/// Reads `length` bytes of the contract's memory at `offset`.
+fn read(caller: &mut Caller, offset: u32, length: u32) -> Result<Vec<u8>, Trap> {
+    let ptr = memory(caller, offset, length)? as *const u8;
+    Ok(unsafe { std::slice::from_raw_parts(ptr, length as usize) }.to_vec())
+}
+
+/// Writes `data` into the contract's memory at `offset`.
+fn write(caller: &mut Caller, offset: u32, data: &[u8]) -> Result<(), Trap> {
+    let ptr = memory(caller, offset, data.len() as u32)? as *mut u8;
+    unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len()) };
+    Ok(())
+}
+
+/// Defines `namespace.name`, and `env.namespace_name` as emitted by the compiler.
+fn define<Params, Args, F>(linker: &mut Linker<State>, namespace: &str, name: &str, func: F)
+where
+    F: IntoFunc<State, Params, Args> + Clone,
+{
+    linker
+        .func_wrap(namespace, name, func.clone())
+        .and_then(|linker| linker.func_wrap("env", &format!("{}_{}", namespace, name), func))
+        .expect("duplicate import definition");
+}
+
+/// Returns a linker providing every imported function.
+fn linker(engine: &Engine) -> Linker<State> {
+    let mut linker = Linker::new(engine);
+    define_ethereum(&mut linker);
+    define_debug(&mut linker);
+    define_bignum(&mut linker);
+    define_eth2(&mut linker);
+    linker
+}
+
+fn define_ethereum(linker: &mut Linker<State>) {
+    define(
+        linker,
+        "ethereum",
+        "useGas",
+        |mut caller: Caller, amount: u64| eei(&mut caller, || unsafe { ethereum_useGas(amount) }),
+    );
+    define(linker, "ethereum", "getGasLeft", |mut caller: Caller| {
+        eei(&mut caller, || unsafe { ethereum_getGasLeft() })
+    });
+    define(
+        linker,
+        "ethereum",
+        "getAddress",
+        |mut caller: Caller, result: u32| {
+            let result = memory(&mut caller, result, 20)?;
+            eei(&mut caller, || unsafe { ethereum_getAddress(result) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getExternalBalance",
+        |mut caller: Caller, address: u32, result: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            let result = memory(&mut caller, result, 16)?;
+            eei(&mut caller, || unsafe {
+                ethereum_getExternalBalance(address, result)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getBlockCoinbase",
+        |mut caller: Caller, result: u32| {
+            let result = memory(&mut caller, result, 20)?;
+            eei(&mut caller, || unsafe { ethereum_getBlockCoinbase(result) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getBlockDifficulty",
+        |mut caller: Caller, result: u32| {
+            let result = memory(&mut caller, result, 32)?;
+            eei(&mut caller, || unsafe {
+                ethereum_getBlockDifficulty(result)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getBlockGasLimit",
+        |mut caller: Caller| eei(&mut caller, || unsafe { ethereum_getBlockGasLimit() }),
+    );
+    define(
+        linker,
+        "ethereum",
+        "getBlockHash",
+        |mut caller: Caller, number: u64, result: u32| {
+            let result = memory(&mut caller, result, 32)?;
+            eei(&mut caller, || unsafe {
+                ethereum_getBlockHash(number, result)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getBlockNumber",
+        |mut caller: Caller| eei(&mut caller, || unsafe { ethereum_getBlockNumber() }),
+    );
+    define(
+        linker,
+        "ethereum",
+        "getBlockTimestamp",
+        |mut caller: Caller| eei(&mut caller, || unsafe { ethereum_getBlockTimestamp() }),
+    );
+    define(
+        linker,
+        "ethereum",
+        "getTxGasPrice",
+        |mut caller: Caller, result: u32| {
+            let result = memory(&mut caller, result, 16)?;
+            eei(&mut caller, || unsafe { ethereum_getTxGasPrice(result) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getTxOrigin",
+        |mut caller: Caller, result: u32| {
+            let result = memory(&mut caller, result, 20)?;
+            eei(&mut caller, || unsafe { ethereum_getTxOrigin(result) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "log",
+        |mut caller: Caller,
+         data: u32,
+         length: u32,
+         count: u32,
+         topic1: u32,
+         topic2: u32,
+         topic3: u32,
+         topic4: u32| {
+            let data = memory(&mut caller, data, length)?;
+            let mut topics = [core::ptr::null(); 4];
+            for (i, topic) in [topic1, topic2, topic3, topic4].iter().enumerate() {
+                if (i as u32) < count {
+                    topics[i] = memory(&mut caller, *topic, 32)?;
+                }
+            }
+            eei(&mut caller, || unsafe {
+                ethereum_log(
+                    data, length, count, topics[0], topics[1], topics[2], topics[3],
+                )
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "call",
+        |mut caller: Caller, gas: u64, address: u32, value: u32, data: u32, length: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            let value = memory(&mut caller, value, 16)?;
+            let data = memory(&mut caller, data, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_call(gas, address, value, data, length)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "callCode",
+        |mut caller: Caller, gas: u64, address: u32, value: u32, data: u32, length: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            let value = memory(&mut caller, value, 16)?;
+            let data = memory(&mut caller, data, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_callCode(gas, address, value, data, length)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "callDelegate",
+        |mut caller: Caller, gas: u64, address: u32, data: u32, length: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            let data = memory(&mut caller, data, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_callDelegate(gas, address, data, length)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "callStatic",
+        |mut caller: Caller, gas: u64, address: u32, data: u32, length: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            let data = memory(&mut caller, data, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_callStatic(gas, address, data, length)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "create",
+        |mut caller: Caller, value: u32, data: u32, length: u32, result: u32| {
+            let value = memory(&mut caller, value, 16)?;
+            let data = memory(&mut caller, data, length)?;
+            let result = memory(&mut caller, result, 20)?;
+            eei(&mut caller, || unsafe {
+                ethereum_create(value, data, length, result)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "returnDataCopy",
+        |mut caller: Caller, result: u32, offset: u32, length: u32| {
+            let result = memory(&mut caller, result, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_returnDataCopy(result, offset, length)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getReturnDataSize",
+        |mut caller: Caller| eei(&mut caller, || unsafe { ethereum_getReturnDataSize() }),
+    );
+    define(
+        linker,
+        "ethereum",
+        "finish",
+        |mut caller: Caller, data: u32, length: u32| {
+            let data = memory(&mut caller, data, length)?;
+            eei::<(), _>(&mut caller, || unsafe { ethereum_finish(data, length) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "revert",
+        |mut caller: Caller, data: u32, length: u32| {
+            let data = memory(&mut caller, data, length)?;
+            eei::<(), _>(&mut caller, || unsafe { ethereum_revert(data, length) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "callDataCopy",
+        |mut caller: Caller, result: u32, offset: u32, length: u32| {
+            let result = memory(&mut caller, result, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_callDataCopy(result, offset, length)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getCallDataSize",
+        |mut caller: Caller| eei(&mut caller, || unsafe { ethereum_getCallDataSize() }),
+    );
+    define(
+        linker,
+        "ethereum",
+        "getCaller",
+        |mut caller: Caller, result: u32| {
+            let result = memory(&mut caller, result, 20)?;
+            eei(&mut caller, || unsafe { ethereum_getCaller(result) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getCallValue",
+        |mut caller: Caller, result: u32| {
+            let result = memory(&mut caller, result, 16)?;
+            eei(&mut caller, || unsafe { ethereum_getCallValue(result) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "codeCopy",
+        |mut caller: Caller, result: u32, offset: u32, length: u32| {
+            let result = memory(&mut caller, result, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_codeCopy(result, offset, length)
+            })
+        },
+    );
+    define(linker, "ethereum", "getCodeSize", |mut caller: Caller| {
+        eei(&mut caller, || unsafe { ethereum_getCodeSize() })
+    });
+    define(
+        linker,
+        "ethereum",
+        "externalCodeCopy",
+        |mut caller: Caller, address: u32, result: u32, offset: u32, length: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            let result = memory(&mut caller, result, length)?;
+            eei(&mut caller, || unsafe {
+                ethereum_externalCodeCopy(address, result, offset, length)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "getExternalCodeSize",
+        |mut caller: Caller, address: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            eei(&mut caller, || unsafe {
+                ethereum_getExternalCodeSize(address)
+            })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "storageLoad",
+        |mut caller: Caller, key: u32, result: u32| {
+            let key = memory(&mut caller, key, 32)?;
+            let result = memory(&mut caller, result, 32)?;
+            eei(&mut caller, || unsafe { ethereum_storageLoad(key, result) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "storageStore",
+        |mut caller: Caller, key: u32, value: u32| {
+            let key = memory(&mut caller, key, 32)?;
+            let value = memory(&mut caller, value, 32)?;
+            eei(&mut caller, || unsafe { ethereum_storageStore(key, value) })
+        },
+    );
+    define(
+        linker,
+        "ethereum",
+        "selfDestruct",
+        |mut caller: Caller, address: u32| {
+            let address = memory(&mut caller, address, 20)?;
+            eei::<(), _>(&mut caller, || unsafe { ethereum_selfDestruct(address) })
+        },
+    );
+}
+
+/// Formats bytes as lowercase hexadecimal.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn define_debug(linker: &mut Linker<State>) {
+    define(linker, "debug", "print32", |value: u32| {
+        println!("{}", value)
+    });
+    define(linker, "debug", "print64", |value: u64| {
+        println!("{}", value)
+    });
+    define(
+        linker,
+        "debug",
+        "printMem",
+        |mut caller: Caller, offset: u32, length: u32| {
+            let data = read(&mut caller, offset, length)?;
+            println!("{}", String::from_utf8_lossy(&data));
+            Ok(())
+        },
+    );
+    define(
+        linker,
+        "debug",
+        "printMemHex",
+        |mut caller: Caller, offset: u32, length: u32| {
+            let data = read(&mut caller, offset, length)?;
+            println!("{}", hex(&data));
+            Ok(())
+        },
+    );
+    define(
+        linker,
+        "debug",
+        "printStorage",
+        |mut caller: Caller, key: u32| {
+            let key = StorageKey::from(&read32(&mut caller, key)?);
+            let value = with_host(|host| host.storage(&host.message.address, &key));
+            println!("{}", String::from_utf8_lossy(&value.bytes));
+            Ok(())
+        },
+    );
+    define(
+        linker,
+        "debug",
+        "printStorageHex",
+        |mut caller: Caller, key: u32| {
+            let key = StorageKey::from(&read32(&mut caller, key)?);
+            let value = with_host(|host| host.storage(&host.message.address, &key));
+            println!("{}", hex(&value.bytes));
+            Ok(())
+        },
+    );
+}
+
+/// Reads 32 bytes of the contract's memory at `offset`.
+fn read32(caller: &mut Caller, offset: u32) -> Result<[u8; 32], Trap> {
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(&read(caller, offset, 32)?);
+    Ok(ret)
+}
+
+/// Splits a little-endian 256-bit integer into 64-bit limbs, least significant first.
+fn limbs(bytes: &[u8; 32]) -> [u64; 4] {
+    let mut ret = [0u64; 4];
+    for (limb, chunk) in ret.iter_mut().zip(bytes.chunks(8)) {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(buf);
+    }
+    ret
+}
+
+/// Joins 64-bit limbs, least significant first, into a little-endian 256-bit integer.
+fn unlimbs(limbs: &[u64]) -> [u8; 32] {
+    let mut ret = [0u8; 32];
+    for (chunk, limb) in ret.chunks_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    ret
+}
+
+/// Returns the full 512-bit product of two 256-bit integers.
+fn mul512(a: &[u64; 4], b: &[u64; 4]) -> [u64; 8] {
+    let mut ret = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let t = u128::from(a[i]) * u128::from(b[j]) + u128::from(ret[i + j]) + carry;
+            ret[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        ret[i + 4] = carry as u64;
+    }
+    ret
+}
+
+/// Returns the remainder of a 512-bit integer divided by a non-zero 256-bit integer.
+fn rem512(n: &[u64; 8], m: &[u64; 4]) -> [u64; 4] {
+    // Shift-and-subtract long division. The remainder needs a fifth limb for the shifted-out bit.
+    let mut r = [0u64; 5];
+    for bit in (0..512).rev() {
+        for i in (1..5).rev() {
+            r[i] = (r[i] << 1) | (r[i - 1] >> 63);
+        }
+        r[0] = (r[0] << 1) | ((n[bit / 64] >> (bit % 64)) & 1);
+
+        let greater_or_equal = r[4] != 0
+            || (0..4).rev().map(|i| r[i].cmp(&m[i])).find(|o| o.is_ne())
+                != Some(core::cmp::Ordering::Less);
+        if greater_or_equal {
+            let mut borrow = false;
+            for i in 0..5 {
+                let m = if i < 4 { m[i] } else { 0 };
+                let (d, b1) = r[i].overflowing_sub(m);
+                let (d, b2) = d.overflowing_sub(borrow as u64);
+                r[i] = d;
+                borrow = b1 || b2;
+            }
+        }
+    }
+    [r[0], r[1], r[2], r[3]]
+}
+
+fn define_bignum(linker: &mut Linker<State>) {
+    define(
+        linker,
+        "bignum",
+        "mul256",
+        |mut caller: Caller, a: u32, b: u32, result: u32| {
+            let a = limbs(&read32(&mut caller, a)?);
+            let b = limbs(&read32(&mut caller, b)?);
+            write(&mut caller, result, &unlimbs(&mul512(&a, &b)[..4]))
+        },
+    );
+    define(
+        linker,
+        "bignum",
+        "umulmod256",
+        |mut caller: Caller, a: u32, b: u32, modulo: u32, result: u32| {
+            let a = limbs(&read32(&mut caller, a)?);
+            let b = limbs(&read32(&mut caller, b)?);
+            let modulo = limbs(&read32(&mut caller, modulo)?);
+            let ret = if modulo == [0; 4] {
+                [0; 4]
+            } else {
+                rem512(&mul512(&a, &b), &modulo)
+            };
+            write(&mut caller, result, &unlimbs(&ret))
+        },
+    );
+}
+
+fn define_eth2(linker: &mut Linker<State>) {
+    define(
+        linker,
+        "eth2",
+        "loadPreStateRoot",
+        |mut caller: Caller, result: u32| {
+            let root = with_host(|host| host.shard.pre_state_root);
+            write(&mut caller, result, &root.bytes)
+        },
+    );
+    define(linker, "eth2", "blockDataSize", || {
+        with_host(|host| host.shard.block_data.len() as u32)
+    });
+    define(
+        linker,
+        "eth2",
+        "blockDataCopy",
+        |mut caller: Caller, result: u32, offset: u32, length: u32| {
+            let data = with_host(|host| {
+                let (offset, length) = (offset as usize, length as usize);
+                offset
+                    .checked_add(length)
+                    .and_then(|end| host.shard.block_data.get(offset..end))
+                    .map(|data| data.to_vec())
+            });
+            match data {
+                Some(data) => write(&mut caller, result, &data),
+                None => Err(Trap::new("out of bounds copy")),
+            }
+        },
+    );
+    define(
+        linker,
+        "eth2",
+        "savePostStateRoot",
+        |mut caller: Caller, root: u32| {
+            let root = Bytes32::from(read32(&mut caller, root)?);
+            with_host(|host| host.shard.post_state_root = root);
+            Ok(())
+        },
+    );
+    define(
+        linker,
+        "eth2",
+        "pushNewDeposit",
+        |mut caller: Caller, deposit: u32, length: u32| {
+            let deposit = read(&mut caller, deposit, length)?;
+            with_host(|host| host.shard.deposits.push(deposit));
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::support::{address, setup};
+    use crate::testing::{self, take_host, ExecutionOutcome};
+    use crate::{
+        call_mutable, external_code_acquire, finish_data, returndata_acquire, CallResult,
+        CreateResult,
+    };
+
+    fn module(source: &str) -> Vec<u8> {
+        wat::parse_str(source).unwrap()
+    }
+
+    /// Stores 0x2a under key zero, then finishes with the bytes "ok".
+    const STORE: &str = r#"
+        (module
+          (import "ethereum" "storageStore" (func $storageStore (param i32 i32)))
+          (import "ethereum" "finish" (func $finish (param i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 32) "\2a")
+          (data (i32.const 64) "ok")
+          (func (export "main")
+            (call $storageStore (i32.const 0) (i32.const 32))
+            (call $finish (i32.const 64) (i32.const 2))))
+    "#;
+
+    #[test]
+    fn finished() {
+        setup();
+        let execution = execute(&module(STORE)).unwrap();
+        assert_eq!(
+            execution.outcome,
+            ExecutionOutcome::Finished(b"ok".to_vec())
+        );
+        let mut value = StorageValue::default();
+        value.bytes[0] = 0x2a;
+        assert_eq!(
+            take_host().storage(&address(1), &StorageKey::default()),
+            value
+        );
+    }
+
+    #[test]
+    fn reverted() {
+        setup();
+        let code = module(
+            r#"
+            (module
+              (import "env" "ethereum_storageStore" (func $storageStore (param i32 i32)))
+              (import "env" "ethereum_revert" (func $revert (param i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 32) "\2a")
+              (func (export "main")
+                (call $storageStore (i32.const 0) (i32.const 32))
+                (call $revert (i32.const 32) (i32.const 1))))
+            "#,
+        );
+        let execution = execute(&code).unwrap();
+        assert_eq!(execution.outcome, ExecutionOutcome::Reverted(vec![0x2a]));
+        assert!(execution.diff.is_empty());
+    }
+
+    #[test]
+    fn trapped() {
+        setup();
+        let code = module(r#"(module (func (export "main") unreachable))"#);
+        assert_eq!(execute(&code).unwrap().outcome, ExecutionOutcome::Aborted);
+
+        // Out of bounds pointers passed to imported functions trap as well.
+        let code = module(
+            r#"
+            (module
+              (import "ethereum" "finish" (func $finish (param i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "main") (call $finish (i32.const 65535) (i32.const 2))))
+            "#,
+        );
+        assert_eq!(execute(&code).unwrap().outcome, ExecutionOutcome::Aborted);
+    }
+
+    #[test]
+    fn out_of_gas() {
+        setup();
+        with_host(|host| host.message.gas = 10);
+        let code = module(
+            r#"
+            (module
+              (import "ethereum" "useGas" (func $useGas (param i64)))
+              (func (export "main") (call $useGas (i64.const 11))))
+            "#,
+        );
+        let execution = execute(&code).unwrap();
+        assert_eq!(execution.outcome, ExecutionOutcome::OutOfGas);
+        assert_eq!(execution.gas_used, 10);
+    }
+
+    #[test]
+    fn nested_calls() {
+        setup();
+        with_host(|host| {
+            host.set_code(&address(2), &module(STORE));
+            host.register_contract(&address(3), || {
+                match call_mutable(100_000, &address(2), &EtherValue::default(), &[]) {
+                    CallResult::Successful => (),
+                    _ => panic!("call failed"),
+                }
+                finish_data(&returndata_acquire());
+            });
+        });
+
+        // A compiled contract calling a native one, which calls a compiled one in turn.
+        let code = module(
+            r#"
+            (module
+              (import "ethereum" "call"
+                (func $call (param i64 i32 i32 i32 i32) (result i32)))
+              (import "ethereum" "getReturnDataSize" (func $getReturnDataSize (result i32)))
+              (import "ethereum" "returnDataCopy" (func $returnDataCopy (param i32 i32 i32)))
+              (import "ethereum" "finish" (func $finish (param i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "\03\03\03\03\03\03\03\03\03\03\03\03\03\03\03\03\03\03\03\03")
+              (func (export "main")
+                (if (call $call (i64.const 200000) (i32.const 0) (i32.const 32) (i32.const 0)
+                      (i32.const 0))
+                  (then unreachable))
+                (call $returnDataCopy (i32.const 64) (i32.const 0) (call $getReturnDataSize))
+                (call $finish (i32.const 64) (call $getReturnDataSize))))
+            "#,
+        );
+        let execution = execute(&code).unwrap();
+        assert_eq!(
+            execution.outcome,
+            ExecutionOutcome::Finished(b"ok".to_vec())
+        );
+        assert!(execution
+            .diff
+            .storage(&address(2), &StorageKey::default())
+            .is_some());
+    }
+
+    #[test]
+    fn create() {
+        setup();
+        let init_code = module(STORE);
+        let execution = testing::execute(|| {
+            let address = match crate::create(&EtherValue::default(), &init_code) {
+                CreateResult::Successful(address) => address,
+                _ => panic!("create failed"),
+            };
+            assert_eq!(external_code_acquire(&address), b"ok".to_vec());
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+    }
+
+    #[test]
+    fn bignum() {
+        let mut a = [0u8; 32];
+        a[..16].copy_from_slice(&u128::MAX.to_le_bytes());
+        let product = mul512(&limbs(&a), &limbs(&a));
+        // (2^128 - 1)^2 = 2^256 - 2^129 + 1
+        assert_eq!(product[..4], [1, 0, u64::MAX - 1, u64::MAX]);
+        assert_eq!(product[4..], [0; 4]);
+
+        let modulo = limbs(&unlimbs(&[1_000_000_007, 0, 0, 0]));
+        let expected = (u128::MAX % 1_000_000_007) * (u128::MAX % 1_000_000_007) % 1_000_000_007;
+        assert_eq!(rem512(&product, &modulo), [expected as u64, 0, 0, 0]);
+        assert_eq!(rem512(&product, &limbs(&a)), [0; 4]);
+    }
+
+    #[test]
+    fn eth2() {
+        setup();
+        with_host(|host| {
+            host.shard.pre_state_root = Bytes32::from([7; 32]);
+            host.shard.block_data = vec![1, 2, 3];
+        });
+        let code = module(
+            r#"
+            (module
+              (import "eth2" "loadPreStateRoot" (func $loadPreStateRoot (param i32)))
+              (import "eth2" "blockDataSize" (func $blockDataSize (result i32)))
+              (import "eth2" "blockDataCopy" (func $blockDataCopy (param i32 i32 i32)))
+              (import "eth2" "savePostStateRoot" (func $savePostStateRoot (param i32)))
+              (import "eth2" "pushNewDeposit" (func $pushNewDeposit (param i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "main")
+                (call $loadPreStateRoot (i32.const 0))
+                (i32.store8 (i32.const 0) (i32.const 8))
+                (call $savePostStateRoot (i32.const 0))
+                (call $blockDataCopy (i32.const 32) (i32.const 1) (i32.const 2))
+                (call $pushNewDeposit (i32.const 32) (call $blockDataSize))))
+            "#,
+        );
+        assert_eq!(
+            execute(&code).unwrap().outcome,
+            ExecutionOutcome::Finished(vec![])
+        );
+
+        let shard = take_host().shard;
+        let mut root = [7; 32];
+        root[0] = 8;
+        assert_eq!(shard.post_state_root, Bytes32::from(root));
+        assert_eq!(shard.deposits, vec![vec![2, 3, 0]]);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(execute(b"\0asm"), Err(Error::InvalidModule(_))));
+        assert!(matches!(
+            execute(&module(
+                r#"(module (import "ethereum" "unknown" (func)) (func (export "main")))"#
+            )),
+            Err(Error::UnknownImport(_))
+        ));
+        assert!(matches!(
+            execute(&module(r#"(module (func (export "main") (param i32)))"#)),
+            Err(Error::MissingMain)
+        ));
+    }
+
+    #[test]
+    fn instantiation_failed() {
+        setup();
+        let code = module(
+            r#"
+            (module
+              (memory 1)
+              (data (i32.const 65535) "ok")
+              (func (export "main")))
+            "#,
+        );
+        assert!(matches!(execute(&code), Err(Error::Instantiation(_))));
+
+        // Changes made by a trapping start function are rolled back.
+        let code = module(
+            r#"
+            (module
+              (import "ethereum" "storageStore" (func $storageStore (param i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 32) "\2a")
+              (func $start
+                (call $storageStore (i32.const 0) (i32.const 32))
+                unreachable)
+              (start $start)
+              (func (export "main")))
+            "#,
+        );
+        assert!(matches!(execute(&code), Err(Error::StartTrapped(_))));
+        assert_eq!(
+            take_host().storage(&address(1), &StorageKey::default()),
+            StorageValue::default()
+        );
+    }
+
+    #[test]
+    fn cached() {
+        let code = module(STORE);
+        let first = load(&code).unwrap();
+        assert!(Rc::ptr_eq(&first, &load(&code).unwrap()));
+        let other = module(r#"(module (func (export "main")))"#);
+        assert!(!Rc::ptr_eq(&first, &load(&other).unwrap()));
+    }
+}
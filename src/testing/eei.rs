@@ -0,0 +1,323 @@
+//! Rust implementations of the EEI functions declared in `native.rs`, operating on the host of the
+//! current thread.
+
+#![allow(non_snake_case)]
+
+use core::{ptr, slice};
+use std::panic;
+
+use super::execution::{call, create};
+use super::gas::GasSchedule;
+use super::host::{CallKind, Halt};
+use super::with_host;
+use crate::types::*;
+
+/// Reads `length` bytes of contract memory at `offset`.
+unsafe fn read<'a>(offset: *const u32, length: usize) -> &'a [u8] {
+    if length == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(offset as *const u8, length)
+    }
+}
+
+/// Writes `data` into contract memory at `offset`.
+unsafe fn write(offset: *const u32, data: &[u8]) {
+    ptr::copy_nonoverlapping(data.as_ptr(), offset as *mut u8, data.len());
+}
+
+unsafe fn read_address(offset: *const u32) -> Address {
+    let mut ret = Address::default();
+    ret.bytes.copy_from_slice(read(offset, 20));
+    ret
+}
+
+unsafe fn read_bytes32<T: From<[u8; 32]>>(offset: *const u32) -> T {
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(read(offset, 32));
+    T::from(ret)
+}
+
+unsafe fn read_value(offset: *const u32) -> EtherValue {
+    let mut ret = EtherValue::default();
+    ret.bytes.copy_from_slice(read(offset, 16));
+    ret
+}
+
+/// Copies `length` bytes of `data` starting at `from` into contract memory. Copying out of bounds
+/// aborts execution, like a trap in the VM would.
+unsafe fn copy(result_offset: *const u32, data: &[u8], from: u32, length: u32) {
+    let (from, length) = (from as usize, length as usize);
+    let end = from
+        .checked_add(length)
+        .filter(|end| *end <= data.len())
+        .expect("out of bounds copy");
+    write(result_offset, &data[from..end]);
+}
+
+/// Charges the gas cost selected from the schedule.
+fn charge<F: FnOnce(&GasSchedule) -> u64>(cost: F) {
+    with_host(|host| host.charge(cost(&host.schedule)))
+}
+
+/// Unwinds the contract with the given reason.
+fn halt(reason: Halt) -> ! {
+    panic::resume_unwind(Box::new(reason))
+}
+
+pub unsafe fn ethereum_useGas(amount: u64) {
+    with_host(|host| host.charge(amount))
+}
+
+pub unsafe fn ethereum_getGasLeft() -> u64 {
+    charge(|schedule| schedule.base);
+    with_host(|host| host.message.gas)
+}
+
+pub unsafe fn ethereum_getAddress(resultOffset: *const u32) {
+    charge(|schedule| schedule.base);
+    let address = with_host(|host| host.message.address);
+    write(resultOffset, &address.bytes);
+}
+
+pub unsafe fn ethereum_getExternalBalance(addressOffset: *const u32, resultOffset: *const u32) {
+    charge(|schedule| schedule.external_balance);
+    let address = read_address(addressOffset);
+    let balance = with_host(|host| host.balance(&address));
+    write(resultOffset, &balance.bytes);
+}
+
+pub unsafe fn ethereum_getBlockCoinbase(resultOffset: *const u32) {
+    charge(|schedule| schedule.base);
+    let coinbase = with_host(|host| host.block.coinbase);
+    write(resultOffset, &coinbase.bytes);
+}
+
+pub unsafe fn ethereum_getBlockDifficulty(resultOffset: *const u32) {
+    charge(|schedule| schedule.base);
+    let difficulty = with_host(|host| host.block.difficulty);
+    write(resultOffset, &difficulty.bytes);
+}
+
+pub unsafe fn ethereum_getBlockGasLimit() -> u64 {
+    charge(|schedule| schedule.base);
+    with_host(|host| host.block.gas_limit)
+}
+
+pub unsafe fn ethereum_getBlockHash(number: u64, resultOffset: *const u32) -> u32 {
+    charge(|schedule| schedule.block_hash);
+    match with_host(|host| host.block.hashes.get(&number).copied()) {
+        Some(hash) => {
+            write(resultOffset, &hash.bytes);
+            0
+        }
+        None => 1,
+    }
+}
+
+pub unsafe fn ethereum_getBlockNumber() -> u64 {
+    charge(|schedule| schedule.base);
+    with_host(|host| host.block.number)
+}
+
+pub unsafe fn ethereum_getBlockTimestamp() -> u64 {
+    charge(|schedule| schedule.base);
+    with_host(|host| host.block.timestamp)
+}
+
+pub unsafe fn ethereum_getTxGasPrice(valueOffset: *const u32) {
+    charge(|schedule| schedule.base);
+    let gas_price = with_host(|host| host.tx.gas_price);
+    write(valueOffset, &gas_price.bytes);
+}
+
+pub unsafe fn ethereum_getTxOrigin(resultOffset: *const u32) {
+    charge(|schedule| schedule.base);
+    let origin = with_host(|host| host.tx.origin);
+    write(resultOffset, &origin.bytes);
+}
+
+pub unsafe fn ethereum_log(
+    dataOffset: *const u32,
+    length: u32,
+    numberOfTopics: u32,
+    topic1: *const u32,
+    topic2: *const u32,
+    topic3: *const u32,
+    topic4: *const u32,
+) {
+    assert!(numberOfTopics <= 4, "too many log topics");
+    let data = read(dataOffset, length as usize);
+    let topics: Vec<LogTopic> = [topic1, topic2, topic3, topic4]
+        .iter()
+        .take(numberOfTopics as usize)
+        .map(|topic| read_bytes32(*topic))
+        .collect();
+    with_host(|host| host.log(data, &topics))
+}
+
+pub unsafe fn ethereum_call(
+    gas: u64,
+    addressOffset: *const u32,
+    valueOffset: *const u32,
+    dataOffset: *const u32,
+    dataLength: u32,
+) -> u32 {
+    let address = read_address(addressOffset);
+    let value = read_value(valueOffset);
+    let data = read(dataOffset, dataLength as usize);
+    call(CallKind::Call, gas, &address, &value, data)
+}
+
+pub unsafe fn ethereum_callCode(
+    gas: u64,
+    addressOffset: *const u32,
+    valueOffset: *const u32,
+    dataOffset: *const u32,
+    dataLength: u32,
+) -> u32 {
+    let address = read_address(addressOffset);
+    let value = read_value(valueOffset);
+    let data = read(dataOffset, dataLength as usize);
+    call(CallKind::CallCode, gas, &address, &value, data)
+}
+
+pub unsafe fn ethereum_callDelegate(
+    gas: u64,
+    addressOffset: *const u32,
+    dataOffset: *const u32,
+    dataLength: u32,
+) -> u32 {
+    let address = read_address(addressOffset);
+    let data = read(dataOffset, dataLength as usize);
+    call(
+        CallKind::Delegate,
+        gas,
+        &address,
+        &EtherValue::default(),
+        data,
+    )
+}
+
+pub unsafe fn ethereum_callStatic(
+    gas: u64,
+    addressOffset: *const u32,
+    dataOffset: *const u32,
+    dataLength: u32,
+) -> u32 {
+    let address = read_address(addressOffset);
+    let data = read(dataOffset, dataLength as usize);
+    call(
+        CallKind::Static,
+        gas,
+        &address,
+        &EtherValue::default(),
+        data,
+    )
+}
+
+pub unsafe fn ethereum_create(
+    valueOffset: *const u32,
+    dataOffset: *const u32,
+    dataLength: u32,
+    resultOffset: *const u32,
+) -> u32 {
+    let value = read_value(valueOffset);
+    let data = read(dataOffset, dataLength as usize);
+    match create(&value, data) {
+        Ok(address) => {
+            write(resultOffset, &address.bytes);
+            0
+        }
+        Err(code) => code,
+    }
+}
+
+pub unsafe fn ethereum_returnDataCopy(resultOffset: *const u32, dataOffset: u32, length: u32) {
+    charge(|schedule| schedule.copy_cost(schedule.copy, length as usize));
+    with_host(|host| copy(resultOffset, host.return_data(), dataOffset, length))
+}
+
+pub unsafe fn ethereum_getReturnDataSize() -> u32 {
+    charge(|schedule| schedule.base);
+    with_host(|host| host.return_data().len() as u32)
+}
+
+pub unsafe fn ethereum_finish(dataOffset: *const u32, length: u32) -> ! {
+    let data = read(dataOffset, length as usize);
+    halt(with_host(|host| host.finish(data)))
+}
+
+pub unsafe fn ethereum_revert(dataOffset: *const u32, length: u32) -> ! {
+    let data = read(dataOffset, length as usize);
+    halt(with_host(|host| host.revert(data)))
+}
+
+pub unsafe fn ethereum_callDataCopy(resultOffset: *const u32, dataOffset: u32, length: u32) {
+    charge(|schedule| schedule.copy_cost(schedule.copy, length as usize));
+    with_host(|host| copy(resultOffset, &host.message.data, dataOffset, length))
+}
+
+pub unsafe fn ethereum_getCallDataSize() -> u32 {
+    charge(|schedule| schedule.base);
+    with_host(|host| host.message.data.len() as u32)
+}
+
+pub unsafe fn ethereum_getCaller(resultOffset: *const u32) {
+    charge(|schedule| schedule.base);
+    let caller = with_host(|host| host.message.caller);
+    write(resultOffset, &caller.bytes);
+}
+
+pub unsafe fn ethereum_getCallValue(resultOffset: *const u32) {
+    charge(|schedule| schedule.base);
+    let value = with_host(|host| host.message.value);
+    write(resultOffset, &value.bytes);
+}
+
+pub unsafe fn ethereum_codeCopy(resultOffset: *const u32, codeOffset: u32, length: u32) {
+    charge(|schedule| schedule.copy_cost(schedule.copy, length as usize));
+    with_host(|host| {
+        let code = host.code(&host.code_address());
+        copy(resultOffset, code, codeOffset, length)
+    })
+}
+
+pub unsafe fn ethereum_getCodeSize() -> u32 {
+    charge(|schedule| schedule.base);
+    with_host(|host| host.code(&host.code_address()).len() as u32)
+}
+
+pub unsafe fn ethereum_externalCodeCopy(
+    addressOffset: *const u32,
+    resultOffset: *const u32,
+    codeOffset: u32,
+    length: u32,
+) {
+    charge(|schedule| schedule.copy_cost(schedule.external_code, length as usize));
+    let address = read_address(addressOffset);
+    with_host(|host| copy(resultOffset, host.code(&address), codeOffset, length))
+}
+
+pub unsafe fn ethereum_getExternalCodeSize(addressOfset: *const u32) -> u32 {
+    charge(|schedule| schedule.external_code);
+    let address = read_address(addressOfset);
+    with_host(|host| host.code(&address).len() as u32)
+}
+
+pub unsafe fn ethereum_storageLoad(keyOffset: *const u32, resultOffset: *const u32) {
+    let key = read_bytes32(keyOffset);
+    let value = with_host(|host| host.storage_load(&key));
+    write(resultOffset, &value.bytes);
+}
+
+pub unsafe fn ethereum_storageStore(keyOffset: *const u32, valueOffset: *const u32) {
+    let key = read_bytes32(keyOffset);
+    let value = read_bytes32(valueOffset);
+    with_host(|host| host.storage_store(&key, &value))
+}
+
+pub unsafe fn ethereum_selfDestruct(addressOffset: *const u32) -> ! {
+    let beneficiary = read_address(addressOffset);
+    halt(with_host(|host| host.selfdestruct(&beneficiary)))
+}
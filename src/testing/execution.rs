@@ -0,0 +1,370 @@
+//! Running contract entry functions and observing how they terminate.
+
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::vec::Vec;
+
+use super::host::{Account, CallKind, Halt};
+use super::logs::{self, Log};
+use super::with_host;
+use crate::types::*;
+
+/// The way an execution terminated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    /// Execution ended with `finish` or `finish_data`, or by returning from the entry function.
+    Finished(Vec<u8>),
+    /// Execution ended with `revert` or `revert_data`.
+    Reverted(Vec<u8>),
+    /// Execution was aborted, either with `abort` or by any other panic.
+    Aborted,
+    /// The contract self-destructed, sending its balance to the given beneficiary.
+    SelfDestructed(Address),
+    /// Execution ran out of gas.
+    OutOfGas,
+}
+
+impl ExecutionOutcome {
+    /// Returns true if the state changes of the execution are kept.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self,
+            ExecutionOutcome::Finished(_) | ExecutionOutcome::SelfDestructed(_)
+        )
+    }
+
+    /// Returns the data returned with `finish_data` or `revert_data`.
+    pub fn output(&self) -> &[u8] {
+        match self {
+            ExecutionOutcome::Finished(data) | ExecutionOutcome::Reverted(data) => data,
+            _ => &[],
+        }
+    }
+}
+
+/// A value before and after execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+/// The changes made to a single account.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub balance: Option<Change<EtherValue>>,
+    pub nonce: Option<Change<u64>>,
+    pub code: Option<Change<Vec<u8>>>,
+    pub storage: BTreeMap<StorageKey, Change<StorageValue>>,
+    /// Set if the account existed before the execution, but not after it.
+    pub destroyed: bool,
+}
+
+/// The changes made to the world state, keyed by account.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct StateDiff {
+    pub accounts: BTreeMap<Address, AccountDiff>,
+}
+
+impl StateDiff {
+    /// Computes the changes between two world states.
+    pub fn new(before: &BTreeMap<Address, Account>, after: &BTreeMap<Address, Account>) -> Self {
+        let empty = Account::default();
+        let mut accounts = BTreeMap::new();
+
+        for address in before.keys().chain(after.keys()) {
+            let pre = before.get(address).unwrap_or(&empty);
+            let post = after.get(address).unwrap_or(&empty);
+
+            let mut diff = AccountDiff {
+                destroyed: before.contains_key(address) && !after.contains_key(address),
+                ..AccountDiff::default()
+            };
+            if pre.balance != post.balance {
+                diff.balance = Some(Change {
+                    before: pre.balance,
+                    after: post.balance,
+                });
+            }
+            if pre.nonce != post.nonce {
+                diff.nonce = Some(Change {
+                    before: pre.nonce,
+                    after: post.nonce,
+                });
+            }
+            if pre.code != post.code {
+                diff.code = Some(Change {
+                    before: pre.code.clone(),
+                    after: post.code.clone(),
+                });
+            }
+            for key in pre.storage.keys().chain(post.storage.keys()) {
+                let old = pre.storage.get(key).copied().unwrap_or_default();
+                let new = post.storage.get(key).copied().unwrap_or_default();
+                if old != new {
+                    diff.storage.insert(
+                        *key,
+                        Change {
+                            before: old,
+                            after: new,
+                        },
+                    );
+                }
+            }
+
+            if diff != AccountDiff::default() {
+                accounts.insert(*address, diff);
+            }
+        }
+
+        StateDiff { accounts }
+    }
+
+    /// Returns true if nothing was changed.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Returns the change of the balance of `address`, if any.
+    pub fn balance(&self, address: &Address) -> Option<&Change<EtherValue>> {
+        self.accounts.get(address)?.balance.as_ref()
+    }
+
+    /// Returns the change of the value stored under `key` by `address`, if any.
+    pub fn storage(&self, address: &Address, key: &StorageKey) -> Option<&Change<StorageValue>> {
+        self.accounts.get(address)?.storage.get(key)
+    }
+}
+
+/// The result of executing an entry function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Execution {
+    pub outcome: ExecutionOutcome,
+    /// The gas used by the execution.
+    pub gas_used: u64,
+    /// The changes made to the world state. Empty unless the execution succeeded.
+    pub diff: StateDiff,
+    /// The logs appended by the execution, in order. Empty unless the execution succeeded.
+    pub logs: Vec<Log>,
+}
+
+impl Execution {
+    /// Asserts that a log with exactly the given topics and data was appended, and returns it.
+    ///
+    /// # Panics
+    /// Panics listing every log if there is no such log.
+    #[track_caller]
+    pub fn assert_logged(&self, topics: &[LogTopic], data: &[u8]) -> &Log {
+        logs::assert_logged(&self.logs, topics, data)
+    }
+}
+
+/// Runs `entry` until it returns or halts.
+pub(crate) fn run<F: FnOnce()>(entry: F) -> ExecutionOutcome {
+    match panic::catch_unwind(AssertUnwindSafe(entry)) {
+        Ok(()) => ExecutionOutcome::Finished(Vec::new()),
+        Err(payload) => match payload.downcast::<Halt>() {
+            Ok(halt) => match *halt {
+                Halt::Finish(data) => ExecutionOutcome::Finished(data),
+                Halt::Revert(data) => ExecutionOutcome::Reverted(data),
+                Halt::SelfDestruct(beneficiary) => ExecutionOutcome::SelfDestructed(beneficiary),
+                Halt::OutOfGas => ExecutionOutcome::OutOfGas,
+            },
+            Err(_) => ExecutionOutcome::Aborted,
+        },
+    }
+}
+
+/// Executes a nested call, dispatching it to the contract registered at the called code address.
+/// Calls to accounts without a registered contract succeed without returning data. Returns the
+/// EEI result code.
+pub(crate) fn call(
+    kind: CallKind,
+    gas: u64,
+    address: &Address,
+    value: &EtherValue,
+    data: &[u8],
+) -> u32 {
+    let outcome = match with_host(|host| host.enter(kind, gas, address, value, data)) {
+        Ok(contract) => {
+            let outcome = match contract {
+                Some(contract) => run(|| contract.run()),
+                None => ExecutionOutcome::Finished(Vec::new()),
+            };
+            with_host(|host| host.leave(&outcome));
+            outcome
+        }
+        Err(outcome) => outcome,
+    };
+    result_code(&outcome)
+}
+
+/// Creates a contract by running `init_code`, which is dispatched like the code of a nested call,
+/// and deploys the code it finishes with. Returns the address of the new contract, or the EEI
+/// result code if it was not created.
+pub(crate) fn create(value: &EtherValue, init_code: &[u8]) -> Result<Address, u32> {
+    let (address, contract) = with_host(|host| host.enter_create(value, init_code))
+        .map_err(|outcome| result_code(&outcome))?;
+    let outcome = match contract {
+        Some(contract) => run(|| contract.run()),
+        None => ExecutionOutcome::Finished(Vec::new()),
+    };
+    with_host(|host| host.leave_create(&address, &outcome));
+
+    match result_code(&outcome) {
+        0 => Ok(address),
+        code => Err(code),
+    }
+}
+
+/// Returns the EEI result code of a nested call or contract creation.
+fn result_code(outcome: &ExecutionOutcome) -> u32 {
+    match outcome {
+        ExecutionOutcome::Finished(_) | ExecutionOutcome::SelfDestructed(_) => 0,
+        ExecutionOutcome::Reverted(_) => 2,
+        ExecutionOutcome::Aborted | ExecutionOutcome::OutOfGas => 1,
+    }
+}
+
+/// Executes a contract entry function against the host of the current thread, as the call
+/// described by its `message`. If the execution does not succeed, all changes to the state are
+/// rolled back. The gas of the message is the budget of each execution, and is left unchanged, so
+/// that an execution which runs out of gas or aborts does not starve the next one.
+///
+/// # Examples
+/// ```
+/// use ewasm_api::prelude::*;
+/// use ewasm_api::testing::{self, ExecutionOutcome};
+///
+/// fn entry() {
+///     if calldata_size() == 0 {
+///         revert_data(b"no input");
+///     }
+///     storage_store(&StorageKey::default(), &StorageValue::from([1; 32]));
+///     finish_data(b"ok");
+/// }
+///
+/// let execution = testing::execute(entry);
+/// assert_eq!(execution.outcome, ExecutionOutcome::Reverted(b"no input".to_vec()));
+/// assert!(execution.diff.is_empty());
+///
+/// testing::with_host(|host| host.message.data = vec![1]);
+/// let execution = testing::execute(entry);
+/// assert_eq!(execution.outcome, ExecutionOutcome::Finished(b"ok".to_vec()));
+/// assert_eq!(execution.diff.accounts.len(), 1);
+/// ```
+pub fn execute<F: FnOnce()>(entry: F) -> Execution {
+    let (accounts, checkpoint, gas, logs) = with_host(|host| {
+        (
+            host.accounts.clone(),
+            host.checkpoint(),
+            host.message.gas,
+            host.logs().len(),
+        )
+    });
+
+    let outcome = run(entry);
+
+    with_host(|host| {
+        if !outcome.is_success() {
+            host.revert_to(checkpoint);
+        }
+        if outcome == ExecutionOutcome::Aborted {
+            host.message.gas = 0;
+        }
+        host.commit();
+        let gas_used = gas - host.message.gas;
+        host.message.gas = gas;
+        Execution {
+            gas_used,
+            diff: StateDiff::new(&accounts, &host.accounts),
+            logs: host.logs()[logs..].to_vec(),
+            outcome,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::support::{address, setup};
+    use crate::testing::take_host;
+    use crate::*;
+
+    fn store_and(halt: fn()) {
+        storage_store(&StorageKey::default(), &StorageValue::from([1; 32]));
+        log0(&[1]);
+        halt()
+    }
+
+    #[test]
+    fn finished() {
+        setup();
+        let execution = execute(|| store_and(|| finish_data(&[1, 2])));
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![1, 2]));
+        assert_eq!(
+            execution.diff.storage(&address(1), &StorageKey::default()),
+            Some(&Change {
+                before: StorageValue::default(),
+                after: StorageValue::from([1; 32]),
+            })
+        );
+        assert_eq!(take_host().logs().len(), 1);
+    }
+
+    #[test]
+    fn returned() {
+        setup();
+        let execution = execute(|| store_and(|| ()));
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+        assert_eq!(execution.diff.accounts.len(), 1);
+    }
+
+    #[test]
+    fn reverted() {
+        setup();
+        let execution = execute(|| store_and(|| revert_data(&[3])));
+        assert_eq!(execution.outcome, ExecutionOutcome::Reverted(vec![3]));
+        assert!(execution.diff.is_empty());
+
+        let host = take_host();
+        assert_eq!(
+            host.storage(&address(1), &StorageKey::default()),
+            StorageValue::default()
+        );
+        assert!(host.logs().is_empty());
+    }
+
+    #[test]
+    fn aborted() {
+        setup();
+        with_host(|host| host.message.gas = 100_000);
+        let execution = execute(|| store_and(|| abort()));
+        assert_eq!(execution.outcome, ExecutionOutcome::Aborted);
+        assert_eq!(execution.gas_used, 100_000);
+        assert!(execution.diff.is_empty());
+
+        // The next execution gets the full budget again.
+        let execution = execute(|| assert_eq!(gas_left(), 100_000 - 2));
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+        assert_eq!(execution.gas_used, 2);
+    }
+
+    #[test]
+    fn selfdestructed() {
+        setup();
+        let execution = execute(|| selfdestruct(&address(2)));
+        assert_eq!(
+            execution.outcome,
+            ExecutionOutcome::SelfDestructed(address(2))
+        );
+        assert!(execution.diff.accounts[&address(1)].destroyed);
+        assert_eq!(
+            execution.diff.balance(&address(2)),
+            Some(&Change {
+                before: EtherValue::default(),
+                after: EtherValue::from(100u128),
+            })
+        );
+    }
+}
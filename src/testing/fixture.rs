@@ -0,0 +1,696 @@
+//! Running state tests from JSON fixtures.
+//!
+//! Fixtures use the layout of the Ethereum GeneralStateTests fillers: every test has an `env`
+//! describing the block, a `pre` state, a `transaction` whose `data`, `gasLimit` and `value` are
+//! lists of alternatives, and a list of `expect` entries. Each entry selects the alternatives it
+//! applies to with `indexes`, and lists the expected `result` state of accounts. Besides the
+//! filler layout, an entry can also list the expected `logs`, each with an `address`, `topics`
+//! and `data`.
+//!
+//! The transaction is executed as a call from its sender to the account at `to`, dispatched like
+//! any nested call. The contract is either registered by the test, or, with the `runner` feature,
+//! the compiled code of the account in the `pre` state. Gas is not paid for, so balances only
+//! reflect value transfers. As the balances of the sender and the coinbase would then never match
+//! those of other clients, expecting either is an error, unless the gas price is zero.
+//!
+//! # Examples
+//! ```ignore
+//! use ewasm_api::testing::fixture;
+//!
+//! #[test]
+//! fn state_tests() {
+//!     fixture::assert_state_tests("tests/fixtures/counter.json", |host| {
+//!         host.register_contract(&COUNTER, counter::entry);
+//!     });
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+
+use serde::Deserialize;
+
+use super::execution::{self, execute};
+use super::host::{Account, CallKind, Host};
+use super::logs::Log;
+use super::{set_host, take_host};
+use crate::types::*;
+
+/// The secret key used by most state tests, and the address of its account.
+const TEST_KEY: (&str, &str) = (
+    "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+    "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b",
+);
+
+/// An error loading or running a fixture.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// A value in the fixture is malformed, or the test is not supported.
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "cannot read fixture: {}", error),
+            Error::Json(error) => write!(f, "cannot parse fixture: {}", error),
+            Error::Invalid(message) => write!(f, "invalid fixture: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+/// A number or string in a fixture. Numbers may be written as JSON numbers, as hexadecimal
+/// strings prefixed with `0x`, or as decimal strings.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum Value {
+    Number(u64),
+    Text(String),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Env {
+    current_coinbase: Value,
+    current_difficulty: Value,
+    current_gas_limit: Value,
+    current_number: Value,
+    current_timestamp: Value,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct AccountState {
+    balance: Option<Value>,
+    nonce: Option<Value>,
+    code: Option<Value>,
+    storage: Option<BTreeMap<String, Value>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionTemplate {
+    data: Vec<Value>,
+    gas_limit: Vec<Value>,
+    gas_price: Option<Value>,
+    sender: Option<Value>,
+    secret_key: Option<Value>,
+    to: Value,
+    value: Vec<Value>,
+}
+
+/// The alternatives an expectation applies to, either all of them (`-1`), one, or several.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum Index {
+    One(i64),
+    Many(Vec<i64>),
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Index::One(-1)
+    }
+}
+
+impl Index {
+    fn matches(&self, index: usize) -> bool {
+        match self {
+            Index::One(-1) => true,
+            Index::One(one) => *one == index as i64,
+            Index::Many(many) => many.contains(&(index as i64)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Indexes {
+    #[serde(default)]
+    data: Index,
+    #[serde(default)]
+    gas: Index,
+    #[serde(default)]
+    value: Index,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct LogState {
+    address: Value,
+    #[serde(default)]
+    topics: Vec<Value>,
+    data: Value,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Expectation {
+    #[serde(default)]
+    indexes: Indexes,
+    #[serde(default)]
+    result: BTreeMap<String, AccountState>,
+    logs: Option<Vec<LogState>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Fixture {
+    env: Env,
+    pre: BTreeMap<String, AccountState>,
+    transaction: TransactionTemplate,
+    expect: Vec<Expectation>,
+}
+
+/// A state test loaded from a fixture.
+#[derive(Clone, Debug)]
+pub struct StateTest {
+    pub name: String,
+    fixture: Fixture,
+}
+
+/// The differences between the expected and actual results of one transaction of a state test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub test: String,
+    /// The indexes of the transaction data, gas limit and value.
+    pub indexes: (usize, usize, usize),
+    /// A description of every difference.
+    pub differences: Vec<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (data, gas, value) = self.indexes;
+        writeln!(
+            f,
+            "{} (data {}, gas {}, value {}):",
+            self.test, data, gas, value
+        )?;
+        for difference in &self.differences {
+            writeln!(f, "  {}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the state tests in a JSON fixture.
+pub fn parse(json: &str) -> Result<Vec<StateTest>, Error> {
+    let fixtures: BTreeMap<String, Fixture> = serde_json::from_str(json)?;
+    Ok(fixtures
+        .into_iter()
+        .map(|(name, fixture)| StateTest { name, fixture })
+        .collect())
+}
+
+/// Loads the state tests in the JSON fixture at `path`.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<StateTest>, Error> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Runs every state test in the fixture at `path`, after preparing each host with `setup`.
+///
+/// # Panics
+/// Panics describing every mismatch if any expectation is not met, or if the fixture is invalid.
+#[track_caller]
+pub fn assert_state_tests<P, F>(path: P, setup: F)
+where
+    P: AsRef<Path>,
+    F: Fn(&mut Host),
+{
+    let mut report = String::new();
+    let tests = load(path).unwrap_or_else(|error| panic!("{}", error));
+    for test in tests {
+        let mismatches = test.run(&setup).unwrap_or_else(|error| panic!("{}", error));
+        for mismatch in mismatches {
+            report.push_str(&mismatch.to_string());
+        }
+    }
+    if !report.is_empty() {
+        panic!("state tests failed:\n{}", report);
+    }
+}
+
+impl StateTest {
+    /// Executes every alternative transaction which has an expectation, each on a fresh host
+    /// prepared with `setup`, and returns the mismatches found.
+    pub fn run<F: Fn(&mut Host)>(&self, setup: F) -> Result<Vec<Mismatch>, Error> {
+        let tx = &self.fixture.transaction;
+        let mut mismatches = Vec::new();
+
+        for data in 0..tx.data.len() {
+            for gas in 0..tx.gas_limit.len() {
+                for value in 0..tx.value.len() {
+                    let expectations: Vec<&Expectation> = self
+                        .fixture
+                        .expect
+                        .iter()
+                        .filter(|expect| {
+                            expect.indexes.data.matches(data)
+                                && expect.indexes.gas.matches(gas)
+                                && expect.indexes.value.matches(value)
+                        })
+                        .collect();
+                    if expectations.is_empty() {
+                        continue;
+                    }
+
+                    let (host, logs) = self.execute(data, gas, value, &setup)?;
+                    let mut differences = Vec::new();
+                    for expect in expectations {
+                        differences.extend(compare(expect, &host, &logs)?);
+                    }
+                    if !differences.is_empty() {
+                        mismatches.push(Mismatch {
+                            test: self.name.clone(),
+                            indexes: (data, gas, value),
+                            differences,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Executes one alternative transaction, returning the final host and the logs appended.
+    fn execute<F: Fn(&mut Host)>(
+        &self,
+        data: usize,
+        gas: usize,
+        value: usize,
+        setup: &F,
+    ) -> Result<(Host, Vec<Log>), Error> {
+        let fixture = &self.fixture;
+        let tx = &fixture.transaction;
+
+        let to = match text(&tx.to) {
+            to if to.is_empty() => {
+                return Err(Error::Invalid("contract creation is not supported".into()))
+            }
+            to => address(&to)?,
+        };
+        let sender = match (&tx.sender, &tx.secret_key) {
+            (Some(sender), _) => address(&text(sender))?,
+            (None, Some(key)) if text(key) == TEST_KEY.0 => address(TEST_KEY.1)?,
+            _ => return Err(Error::Invalid("unknown transaction sender".into())),
+        };
+        let data = bytes(&tx.data[data])?;
+        let gas = uint64(&tx.gas_limit[gas])?;
+        let value = ether(&tx.value[value])?;
+
+        let mut host = Host::new();
+        for (address_text, state) in &fixture.pre {
+            host.accounts
+                .insert(address(address_text)?, account(state)?);
+        }
+        host.block.coinbase = address(&text(&fixture.env.current_coinbase))?;
+        host.block.difficulty = Difficulty::from(number(&fixture.env.current_difficulty)?);
+        host.block.gas_limit = uint64(&fixture.env.current_gas_limit)?;
+        host.block.number = uint64(&fixture.env.current_number)?;
+        host.block.timestamp = uint64(&fixture.env.current_timestamp)?;
+        host.tx.origin = sender;
+        if let Some(price) = &tx.gas_price {
+            host.tx.gas_price = ether(price)?;
+        }
+        host.message.caller = sender;
+        host.message.address = sender;
+        setup(&mut host);
+
+        set_host(host);
+        let execution = execute(|| {
+            execution::call(CallKind::Call, gas, &to, &value, &data);
+        });
+        Ok((take_host(), execution.logs))
+    }
+}
+
+/// Describes every way in which the state of `host` and `logs` differ from `expect`.
+fn compare(expect: &Expectation, host: &Host, logs: &[Log]) -> Result<Vec<String>, Error> {
+    let mut differences = Vec::new();
+    let empty = Account::default();
+
+    for (address_text, state) in &expect.result {
+        let address = address(address_text)?;
+        let actual = host.accounts.get(&address).unwrap_or(&empty);
+        let expected = account(state)?;
+        let name = address.to_string();
+
+        let paid_fees = host.tx.gas_price != EtherValue::default()
+            && (address == host.tx.origin || address == host.block.coinbase);
+        if state.balance.is_some() && paid_fees {
+            return Err(Error::Invalid(format!(
+                "the balance of {} depends on transaction fees, which are not charged",
+                name
+            )));
+        }
+        if state.balance.is_some() && expected.balance != actual.balance {
+            differences.push(format!(
+                "{} balance: expected {:#x}, got {:#x}",
+                name,
+                u128::from(expected.balance),
+                u128::from(actual.balance)
+            ));
+        }
+        if state.code.is_some() && expected.code != actual.code {
+            differences.push(format!(
+                "{} code: expected {}, got {}",
+                name,
+                hex(&expected.code),
+                hex(&actual.code)
+            ));
+        }
+        if state.storage.is_some() {
+            let keys = expected.storage.keys().chain(actual.storage.keys());
+            let mut keys: Vec<&StorageKey> = keys.collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let want = expected.storage.get(key).copied().unwrap_or_default();
+                let got = actual.storage.get(key).copied().unwrap_or_default();
+                if want != got {
+                    differences.push(format!(
+                        "{} storage {}: expected {}, got {}",
+                        name,
+                        hex_number(&key.bytes),
+                        hex_number(&want.bytes),
+                        hex_number(&got.bytes)
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(expected) = &expect.logs {
+        let expected = expected.iter().map(log).collect::<Result<Vec<_>, _>>()?;
+        if expected != logs {
+            differences.push(format!(
+                "logs: expected {}, got {}",
+                describe_logs(&expected),
+                describe_logs(logs)
+            ));
+        }
+    }
+
+    Ok(differences)
+}
+
+fn describe_logs(logs: &[Log]) -> String {
+    let logs: Vec<String> = logs
+        .iter()
+        .map(|log| {
+            let topics: Vec<String> = log.topics.iter().map(|t| t.to_string()).collect();
+            format!(
+                "{{address: {}, topics: [{}], data: {}}}",
+                log.address,
+                topics.join(", "),
+                hex(&log.data)
+            )
+        })
+        .collect();
+    format!("[{}]", logs.join(", "))
+}
+
+/// Formats bytes as hexadecimal, prefixed with `0x`.
+fn hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", digits)
+}
+
+/// Formats big-endian bytes as a hexadecimal number without leading zeros.
+fn hex_number(bytes: &[u8; 32]) -> String {
+    format!("{:#x}", Uint256::from_be_bytes(*bytes))
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Number(number) => number.to_string(),
+        Value::Text(text) => text.clone(),
+    }
+}
+
+/// Parses hexadecimal bytes, with or without a `0x` prefix.
+fn bytes(value: &Value) -> Result<Vec<u8>, Error> {
+    let text = text(value);
+    let digits = text.strip_prefix("0x").unwrap_or(&text);
+    if !digits.len().is_multiple_of(2) {
+        return Err(Error::Invalid(format!("odd number of digits in {}", text)));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| Error::Invalid(format!("invalid hexadecimal {}", text)))
+        })
+        .collect()
+}
+
+/// Parses an address in any case. Fixtures do not checksum their addresses.
+fn address(text: &str) -> Result<Address, Error> {
+    text.parse()
+        .map_err(|_| Error::Invalid(format!("invalid address {}", text)))
+}
+
+/// Parses an unsigned 256-bit number, returning its big-endian bytes.
+fn number(value: &Value) -> Result<[u8; 32], Error> {
+    let text = text(value);
+    let number: Uint256 = text
+        .parse()
+        .map_err(|_| Error::Invalid(format!("invalid number {}", text)))?;
+    Ok(number.to_be_bytes())
+}
+
+fn uint64(value: &Value) -> Result<u64, Error> {
+    u64::try_from(Uint256::from_be_bytes(number(value)?))
+        .map_err(|_| Error::Invalid(format!("{} does not fit 64 bits", text(value))))
+}
+
+/// Parses an amount of wei.
+fn ether(value: &Value) -> Result<EtherValue, Error> {
+    EtherValue::try_from(Uint256::from_be_bytes(number(value)?))
+        .map_err(|_| Error::Invalid(format!("{} does not fit 128 bits", text(value))))
+}
+
+fn account(state: &AccountState) -> Result<Account, Error> {
+    let mut account = Account::default();
+    if let Some(balance) = &state.balance {
+        account.balance = ether(balance)?;
+    }
+    if let Some(nonce) = &state.nonce {
+        account.nonce = uint64(nonce)?;
+    }
+    if let Some(code) = &state.code {
+        account.code = bytes(code)?;
+    }
+    for (key, value) in state.storage.iter().flatten() {
+        let value = StorageValue::from(number(value)?);
+        if value != StorageValue::default() {
+            let key = StorageKey::from(number(&Value::Text(key.clone()))?);
+            account.storage.insert(key, value);
+        }
+    }
+    Ok(account)
+}
+
+fn log(state: &LogState) -> Result<Log, Error> {
+    Ok(Log {
+        address: address(&text(&state.address))?,
+        topics: state
+            .topics
+            .iter()
+            .map(|topic| number(topic).map(LogTopic::from))
+            .collect::<Result<_, _>>()?,
+        data: bytes(&state.data)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calldata_acquire, log1, storage_store};
+
+    const CONTRACT: &str = "0x1000000000000000000000000000000000000000";
+
+    /// Stores its calldata under key 1, logs it and returns.
+    fn contract() {
+        let data = calldata_acquire();
+        let mut value = StorageValue::default();
+        value.bytes[32 - data.len()..].copy_from_slice(&data);
+        let mut key = StorageKey::default();
+        key.bytes[31] = 1;
+        storage_store(&key, &value);
+        log1(&data, &LogTopic::from(value));
+    }
+
+    fn setup(host: &mut Host) {
+        host.register_contract(&address(CONTRACT).unwrap(), contract);
+    }
+
+    fn fixture(expect: &str) -> StateTest {
+        let json = format!(
+            r#"{{
+              "store": {{
+                "env": {{
+                  "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+                  "currentDifficulty": "0x020000",
+                  "currentGasLimit": 10000000,
+                  "currentNumber": "1",
+                  "currentTimestamp": "1000"
+                }},
+                "pre": {{
+                  "{contract}": {{ "balance": "0", "code": "", "nonce": "0", "storage": {{}} }},
+                  "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": {{
+                    "balance": "1000000000000000000", "code": "", "nonce": "0", "storage": {{}}
+                  }}
+                }},
+                "transaction": {{
+                  "data": ["0x01", "0x0202"],
+                  "gasLimit": ["400000"],
+                  "gasPrice": "10",
+                  "nonce": "0",
+                  "secretKey": "{key}",
+                  "to": "{contract}",
+                  "value": ["0", "0x0a"]
+                }},
+                "expect": {expect}
+              }}
+            }}"#,
+            contract = CONTRACT,
+            key = TEST_KEY.0,
+            expect = expect
+        );
+        parse(&json).unwrap().remove(0)
+    }
+
+    #[test]
+    fn expectations_met() {
+        let test = fixture(
+            r#"[
+              {
+                "indexes": { "data": 0, "gas": -1, "value": [0, 1] },
+                "result": { "0x1000000000000000000000000000000000000000": { "storage": { "0x01": "0x01" } } },
+                "logs": [
+                  {
+                    "address": "0x1000000000000000000000000000000000000000",
+                    "topics": ["0x01"],
+                    "data": "0x01"
+                  }
+                ]
+              },
+              {
+                "indexes": { "data": 1, "value": 1 },
+                "result": {
+                  "0x1000000000000000000000000000000000000000": { "balance": "10", "storage": { "1": "514" } }
+                }
+              }
+            ]"#,
+        );
+        assert_eq!(test.name, "store");
+        assert_eq!(test.run(setup).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn mismatches() {
+        let test = fixture(
+            r#"[
+              {
+                "indexes": { "data": 1, "value": 0 },
+                "result": {
+                  "0x1000000000000000000000000000000000000000": { "balance": "1", "storage": { "0x01": "0x0203", "0x02": "0x01" } }
+                },
+                "logs": []
+              }
+            ]"#,
+        );
+        let mismatches = test.run(setup).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].indexes, (1, 0, 0));
+        assert_eq!(
+            mismatches[0].differences[..3],
+            [
+                format!("{} balance: expected 0x1, got 0x0", CONTRACT),
+                format!("{} storage 0x1: expected 0x203, got 0x202", CONTRACT),
+                format!("{} storage 0x2: expected 0x1, got 0x0", CONTRACT),
+            ]
+        );
+        assert!(mismatches[0].differences[3].starts_with("logs: expected [], got [{address: "));
+        assert!(mismatches[0]
+            .to_string()
+            .starts_with("store (data 1, gas 0, value 0):\n"));
+    }
+
+    #[test]
+    fn fee_dependent_balances() {
+        for account in &[TEST_KEY.1, "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba"] {
+            let expect = format!(
+                r#"[{{ "result": {{ "{}": {{ "balance": "0" }} }} }}]"#,
+                account
+            );
+            let error = fixture(&expect).run(setup).unwrap_err();
+            assert!(error.to_string().contains("transaction fees"), "{}", error);
+
+            // Without fees, only value transfers change the balances.
+            let free = |host: &mut Host| {
+                setup(host);
+                host.tx.gas_price = EtherValue::default();
+            };
+            let expect = format!(
+                r#"[{{ "indexes": {{ "value": 0 }}, "result": {{ "{}": {{ "balance": "{}" }} }} }}]"#,
+                account,
+                if *account == TEST_KEY.1 {
+                    "1000000000000000000"
+                } else {
+                    "0"
+                }
+            );
+            assert_eq!(fixture(&expect).run(free).unwrap(), vec![]);
+        }
+    }
+
+    #[test]
+    fn load_from_disk() {
+        let path =
+            std::env::temp_dir().join(format!("fixture-{:?}.json", std::thread::current().id()));
+        fs::write(&path, r#"{ "empty": { "env": {}, "pre": {} } }"#).unwrap();
+        assert!(matches!(load(&path), Err(Error::Json(_))));
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(load(&path), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn numbers() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(number(&Value::Text(max.into())).unwrap(), [0xff; 32]);
+        assert!(number(&Value::Text(format!("{}0", max))).is_err());
+        assert_eq!(uint64(&Value::Text("0x0100".into())).unwrap(), 256);
+        assert_eq!(uint64(&Value::Number(7)).unwrap(), 7);
+        assert!(bytes(&Value::Text("0x123".into())).is_err());
+    }
+
+    #[test]
+    fn addresses() {
+        let expected = address(TEST_KEY.1).unwrap();
+        let mixed = "0xA94F5374Fce5edBC8E2a8697C15331677e6EbF0B";
+        assert_eq!(address(mixed).unwrap(), expected);
+        assert_eq!(address(&mixed.to_ascii_uppercase()[2..]).unwrap(), expected);
+        assert!(address("0xa94f5374fce5edbc8e2a8697c15331677e6ebf").is_err());
+    }
+}
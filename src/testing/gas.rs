@@ -0,0 +1,71 @@
+//! The gas costs charged by the test host.
+
+/// The gas cost of each EEI function. The default values are those of the ewasm
+/// [EEI specification](https://github.com/ewasm/design/blob/master/eth_interface.md).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasSchedule {
+    /// The cost of functions returning a value from the environment, such as `getCaller`.
+    pub base: u64,
+    pub external_balance: u64,
+    pub block_hash: u64,
+    /// The cost of `getExternalCodeSize`, and the base cost of `externalCodeCopy`.
+    pub external_code: u64,
+    /// The base cost of `callDataCopy`, `codeCopy` and `returnDataCopy`.
+    pub copy: u64,
+    /// The cost of every 32-byte word copied.
+    pub copy_word: u64,
+    pub storage_load: u64,
+    /// The cost of storing a non-zero value in an empty slot.
+    pub storage_set: u64,
+    /// The cost of any other storage write.
+    pub storage_reset: u64,
+    pub log: u64,
+    pub log_topic: u64,
+    pub log_byte: u64,
+    pub call: u64,
+    /// The additional cost of a call transferring a non-zero value.
+    pub call_value: u64,
+    /// The additional cost of a call transferring a non-zero value to a new account.
+    pub new_account: u64,
+    pub create: u64,
+    pub selfdestruct: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        GasSchedule {
+            base: 2,
+            external_balance: 400,
+            block_hash: 800,
+            external_code: 700,
+            copy: 3,
+            copy_word: 3,
+            storage_load: 200,
+            storage_set: 20000,
+            storage_reset: 5000,
+            log: 375,
+            log_topic: 375,
+            log_byte: 8,
+            call: 700,
+            call_value: 9000,
+            new_account: 25000,
+            create: 32000,
+            selfdestruct: 5000,
+        }
+    }
+}
+
+impl GasSchedule {
+    /// Returns the cost of copying `length` bytes, on top of `base`.
+    pub fn copy_cost(&self, base: u64, length: usize) -> u64 {
+        let words = (length as u64).div_ceil(32);
+        base.saturating_add(self.copy_word.saturating_mul(words))
+    }
+
+    /// Returns the cost of a log with `topics` topics and `length` bytes of data.
+    pub fn log_cost(&self, topics: usize, length: usize) -> u64 {
+        self.log
+            .saturating_add(self.log_topic.saturating_mul(topics as u64))
+            .saturating_add(self.log_byte.saturating_mul(length as u64))
+    }
+}
@@ -0,0 +1,629 @@
+//! The world state and execution context backing the test host.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use super::execution::ExecutionOutcome;
+use super::gas::GasSchedule;
+use super::journal::{Entry, Journal};
+use super::logs::Log;
+use crate::types::*;
+
+/// The maximum depth of nested calls.
+const MAX_DEPTH: usize = 1024;
+
+/// An account in the world state.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub balance: EtherValue,
+    /// The number of contracts created by the account.
+    pub nonce: u64,
+    pub code: Vec<u8>,
+    pub storage: BTreeMap<StorageKey, StorageValue>,
+}
+
+/// The block the transaction is executed in.
+#[derive(Default, Clone, Debug)]
+pub struct Block {
+    pub coinbase: Address,
+    pub difficulty: Difficulty,
+    pub gas_limit: u64,
+    pub number: u64,
+    pub timestamp: u64,
+    /// The hashes returned by `block_hash`, keyed by block number.
+    pub hashes: BTreeMap<u64, Hash>,
+}
+
+/// The transaction being executed.
+#[derive(Default, Clone, Debug)]
+pub struct Transaction {
+    pub origin: Address,
+    pub gas_price: EtherValue,
+}
+
+/// The state of an Eth 2 shard, used by the `eth2` functions when running compiled contracts.
+#[derive(Default, Clone, Debug)]
+pub struct Shard {
+    pub pre_state_root: Bytes32,
+    pub block_data: Vec<u8>,
+    pub post_state_root: Bytes32,
+    pub deposits: Vec<Vec<u8>>,
+}
+
+/// The parameters of the currently executing call.
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub caller: Address,
+    pub address: Address,
+    pub value: EtherValue,
+    pub data: Vec<u8>,
+    /// The gas left in the call. Unlimited by default.
+    pub gas: u64,
+}
+
+impl Default for Message {
+    fn default() -> Self {
+        Message {
+            caller: Address::default(),
+            address: Address::default(),
+            value: EtherValue::default(),
+            data: Vec::new(),
+            gas: u64::MAX,
+        }
+    }
+}
+
+/// The kind of call made through the EEI.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CallKind {
+    Call,
+    CallCode,
+    Delegate,
+    Static,
+}
+
+/// A contract implemented in Rust, registered with [`Host::register_contract`].
+#[derive(Clone)]
+pub(crate) struct Contract(Rc<dyn Fn()>);
+
+impl Contract {
+    pub(crate) fn run(&self) {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for Contract {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Contract")
+    }
+}
+
+/// The parts of a call frame which are not visible in its message.
+#[derive(Default, Clone, Debug)]
+struct Context {
+    /// The account whose code is executed, if different from the message address.
+    code_address: Option<Address>,
+    is_static: bool,
+}
+
+/// A suspended call frame, resumed when the nested call returns.
+#[derive(Clone, Debug)]
+struct Frame {
+    message: Message,
+    context: Context,
+    checkpoint: usize,
+}
+
+/// The reason execution was halted by the contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Halt {
+    Finish(Vec<u8>),
+    Revert(Vec<u8>),
+    SelfDestruct(Address),
+    OutOfGas,
+}
+
+/// An in-memory Ethereum environment which the EEI functions operate on.
+///
+/// The `message` describes the currently executing call. While a nested call is executed, it is
+/// replaced by the message of that call.
+#[derive(Default, Clone, Debug)]
+pub struct Host {
+    pub accounts: BTreeMap<Address, Account>,
+    pub block: Block,
+    pub tx: Transaction,
+    pub message: Message,
+    /// The gas charged for each EEI function.
+    pub schedule: GasSchedule,
+    pub shard: Shard,
+    context: Context,
+    frames: Vec<Frame>,
+    contracts: BTreeMap<Address, Contract>,
+    journal: Journal,
+    return_data: Vec<u8>,
+    logs: Vec<Log>,
+}
+
+impl Host {
+    /// Creates an empty host.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the account at `address`, creating it if it does not exist.
+    pub fn account_mut(&mut self, address: &Address) -> &mut Account {
+        self.accounts.entry(*address).or_default()
+    }
+
+    /// Returns the balance of `address`.
+    pub fn balance(&self, address: &Address) -> EtherValue {
+        self.accounts
+            .get(address)
+            .map(|account| account.balance)
+            .unwrap_or_default()
+    }
+
+    /// Sets the balance of `address`.
+    pub fn set_balance(&mut self, address: &Address, balance: EtherValue) {
+        self.account_mut(address).balance = balance;
+    }
+
+    /// Returns the code deployed at `address`.
+    pub fn code(&self, address: &Address) -> &[u8] {
+        self.accounts
+            .get(address)
+            .map(|account| account.code.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the nonce of `address`.
+    pub fn nonce(&self, address: &Address) -> u64 {
+        self.accounts
+            .get(address)
+            .map(|account| account.nonce)
+            .unwrap_or_default()
+    }
+
+    /// Deploys `code` at `address`.
+    pub fn set_code(&mut self, address: &Address, code: &[u8]) {
+        self.account_mut(address).code = code.to_vec();
+    }
+
+    /// Registers a Rust function as the contract at `address`. Calls made to `address`, or to
+    /// another account with the code of `address`, will execute it instead of the code deployed
+    /// at `address`.
+    pub fn register_contract<F>(&mut self, address: &Address, entry: F)
+    where
+        F: Fn() + 'static,
+    {
+        self.contracts.insert(*address, Contract(Rc::new(entry)));
+    }
+
+    /// Returns the value stored under `key` in the storage of `address`.
+    pub fn storage(&self, address: &Address, key: &StorageKey) -> StorageValue {
+        self.accounts
+            .get(address)
+            .and_then(|account| account.storage.get(key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Stores `value` under `key` in the storage of `address`. Storing zero clears the slot.
+    pub fn set_storage(&mut self, address: &Address, key: &StorageKey, value: &StorageValue) {
+        let storage = &mut self.account_mut(address).storage;
+        if *value == StorageValue::default() {
+            storage.remove(key);
+        } else {
+            storage.insert(*key, *value);
+        }
+    }
+
+    /// Returns the logs appended so far, in order.
+    pub fn logs(&self) -> &[Log] {
+        &self.logs
+    }
+
+    /// Returns the contents of the return data buffer.
+    pub fn return_data(&self) -> &[u8] {
+        &self.return_data
+    }
+
+    /// Returns a checkpoint which the state can later be reverted to.
+    pub(crate) fn checkpoint(&self) -> usize {
+        self.journal.checkpoint()
+    }
+
+    /// Undoes every change made by the EEI functions since `checkpoint`.
+    pub(crate) fn revert_to(&mut self, checkpoint: usize) {
+        self.journal
+            .revert(checkpoint, &mut self.accounts, &mut self.logs);
+    }
+
+    /// Makes every change made by the EEI functions permanent.
+    pub(crate) fn commit(&mut self) {
+        self.journal.clear();
+    }
+
+    /// Halts the contract if the current call is static, as it must not change the state.
+    fn check_static(&self, operation: &str) {
+        assert!(
+            !self.context.is_static,
+            "{} is not allowed in a static call",
+            operation
+        );
+    }
+
+    /// Returns the account at `address`, creating it in the journal if it does not exist.
+    fn touch(&mut self, address: &Address) -> &mut Account {
+        if !self.accounts.contains_key(address) {
+            self.journal.record(Entry::Created(*address));
+        }
+        self.account_mut(address)
+    }
+
+    fn write_balance(&mut self, address: &Address, balance: EtherValue) {
+        let previous = self.balance(address);
+        self.touch(address);
+        self.journal.record(Entry::Balance(*address, previous));
+        self.set_balance(address, balance);
+    }
+
+    fn write_nonce(&mut self, address: &Address, nonce: u64) {
+        let previous = self.nonce(address);
+        self.touch(address);
+        self.journal.record(Entry::Nonce(*address, previous));
+        self.account_mut(address).nonce = nonce;
+    }
+
+    fn write_code(&mut self, address: &Address, code: &[u8]) {
+        let previous = self.touch(address).code.clone();
+        self.journal.record(Entry::Code(*address, previous));
+        self.set_code(address, code);
+    }
+
+    fn write_storage(&mut self, address: &Address, key: &StorageKey, value: &StorageValue) {
+        let previous = self.storage(address, key);
+        self.touch(address);
+        self.journal
+            .record(Entry::Storage(*address, *key, previous));
+        self.set_storage(address, key, value);
+    }
+
+    /// Subtracts `amount` from the gas left in the current call, halting the contract if there is
+    /// not enough.
+    pub(crate) fn charge(&mut self, amount: u64) {
+        match self.message.gas.checked_sub(amount) {
+            Some(gas) => self.message.gas = gas,
+            None => {
+                self.message.gas = 0;
+                panic::resume_unwind(Box::new(Halt::OutOfGas));
+            }
+        }
+    }
+
+    pub(crate) fn storage_load(&mut self, key: &StorageKey) -> StorageValue {
+        self.charge(self.schedule.storage_load);
+        self.storage(&self.message.address, key)
+    }
+
+    pub(crate) fn storage_store(&mut self, key: &StorageKey, value: &StorageValue) {
+        self.check_static("storage_store");
+        let address = self.message.address;
+        let empty = StorageValue::default();
+        if self.storage(&address, key) == empty && *value != empty {
+            self.charge(self.schedule.storage_set);
+        } else {
+            self.charge(self.schedule.storage_reset);
+        }
+        self.write_storage(&address, key, value);
+    }
+
+    pub(crate) fn log(&mut self, data: &[u8], topics: &[LogTopic]) {
+        self.check_static("log");
+        self.charge(self.schedule.log_cost(topics.len(), data.len()));
+        self.journal.record(Entry::Log);
+        self.logs.push(Log {
+            address: self.message.address,
+            topics: topics.to_vec(),
+            data: data.to_vec(),
+        });
+    }
+
+    /// Returns the account whose code is currently executing.
+    pub(crate) fn code_address(&self) -> Address {
+        self.context.code_address.unwrap_or(self.message.address)
+    }
+
+    /// Sets up the frame for a nested call and returns the contract to execute, if any. If the
+    /// call cannot be made, no frame is entered and the outcome is returned instead.
+    pub(crate) fn enter(
+        &mut self,
+        kind: CallKind,
+        gas: u64,
+        address: &Address,
+        value: &EtherValue,
+        data: &[u8],
+    ) -> Result<Option<Contract>, ExecutionOutcome> {
+        self.return_data.clear();
+
+        if self.frames.len() >= MAX_DEPTH {
+            return Err(ExecutionOutcome::Aborted);
+        }
+
+        let current = self.message.address;
+        let transfers_value = match kind {
+            CallKind::Call | CallKind::CallCode => *value != EtherValue::default(),
+            CallKind::Delegate | CallKind::Static => false,
+        };
+        if kind == CallKind::Call && transfers_value {
+            self.check_static("call with value");
+        }
+
+        let mut cost = self.schedule.call;
+        if transfers_value {
+            cost = cost.saturating_add(self.schedule.call_value);
+            if kind == CallKind::Call && !self.accounts.contains_key(address) {
+                cost = cost.saturating_add(self.schedule.new_account);
+            }
+        }
+        self.charge(cost);
+
+        if transfers_value && self.balance(&current) < *value {
+            return Err(ExecutionOutcome::Aborted);
+        }
+
+        // Forward at most all but one 64th of the gas left, as specified by EIP-150.
+        let gas = gas.min(self.message.gas - self.message.gas / 64);
+        self.message.gas -= gas;
+
+        let (message, context) = match kind {
+            CallKind::Call => (
+                Message {
+                    caller: current,
+                    address: *address,
+                    value: *value,
+                    data: data.to_vec(),
+                    gas,
+                },
+                Context {
+                    code_address: None,
+                    is_static: self.context.is_static,
+                },
+            ),
+            CallKind::CallCode => (
+                Message {
+                    caller: current,
+                    address: current,
+                    value: *value,
+                    data: data.to_vec(),
+                    gas,
+                },
+                Context {
+                    code_address: Some(*address),
+                    is_static: self.context.is_static,
+                },
+            ),
+            CallKind::Delegate => (
+                Message {
+                    caller: self.message.caller,
+                    address: current,
+                    value: self.message.value,
+                    data: data.to_vec(),
+                    gas,
+                },
+                Context {
+                    code_address: Some(*address),
+                    is_static: self.context.is_static,
+                },
+            ),
+            CallKind::Static => (
+                Message {
+                    caller: current,
+                    address: *address,
+                    value: EtherValue::default(),
+                    data: data.to_vec(),
+                    gas,
+                },
+                Context {
+                    code_address: None,
+                    is_static: true,
+                },
+            ),
+        };
+
+        self.push_frame(message, context);
+
+        if transfers_value {
+            let to = self.message.address;
+            if !self.transfer(&current, &to, value) {
+                self.leave(&ExecutionOutcome::Aborted);
+                return Err(ExecutionOutcome::Aborted);
+            }
+        }
+
+        let code_address = self.code_address();
+        Ok(self.dispatch(&code_address, self.code(&code_address)))
+    }
+
+    /// Suspends the current frame and makes `message` the currently executing call.
+    fn push_frame(&mut self, message: Message, context: Context) {
+        let frame = Frame {
+            message: std::mem::replace(&mut self.message, message),
+            context: std::mem::replace(&mut self.context, context),
+            checkpoint: self.journal.checkpoint(),
+        };
+        self.frames.push(frame);
+    }
+
+    /// Returns the contract executing `code` on behalf of `code_address`: the one registered at
+    /// `code_address`, or with the `runner` feature, `code` run as a wasm module.
+    fn dispatch(&self, code_address: &Address, code: &[u8]) -> Option<Contract> {
+        let contract = self.contracts.get(code_address).cloned();
+        #[cfg(feature = "runner")]
+        let contract = contract.or_else(|| {
+            if code.is_empty() {
+                None
+            } else {
+                let code = code.to_vec();
+                Some(Contract(Rc::new(move || crate::runner::run(&code))))
+            }
+        });
+        #[cfg(not(feature = "runner"))]
+        let _ = code;
+        contract
+    }
+
+    /// Returns from a nested call to its caller, rolling back its changes unless it succeeded. The
+    /// gas left is refunded to the caller, unless the call was aborted.
+    pub(crate) fn leave(&mut self, outcome: &ExecutionOutcome) {
+        let frame = self.frames.pop().expect("no call frame to leave");
+        let gas = match outcome {
+            ExecutionOutcome::Aborted | ExecutionOutcome::OutOfGas => 0,
+            _ => self.message.gas,
+        };
+        self.message = frame.message;
+        self.message.gas += gas;
+        self.context = frame.context;
+        if !outcome.is_success() {
+            self.revert_to(frame.checkpoint);
+        }
+        self.return_data = outcome.output().to_vec();
+    }
+
+    /// Sets up the frame for running `init_code` to create a contract, and returns the address of
+    /// the new contract and the contract to execute, if any. If the contract cannot be created, no
+    /// frame is entered and the outcome is returned instead.
+    ///
+    /// # Panics
+    /// Panics if `init_code` is not empty, but there is no way to run it: the `runner` feature is
+    /// disabled and no contract is registered at the new address.
+    pub(crate) fn enter_create(
+        &mut self,
+        value: &EtherValue,
+        init_code: &[u8],
+    ) -> Result<(Address, Option<Contract>), ExecutionOutcome> {
+        self.check_static("create");
+        self.charge(self.schedule.create);
+        self.return_data.clear();
+
+        let creator = self.message.address;
+        if self.frames.len() >= MAX_DEPTH || self.balance(&creator) < *value {
+            return Err(ExecutionOutcome::Aborted);
+        }
+        let nonce = self.nonce(&creator);
+        let address = create_address(&creator, nonce);
+        let contract = self.dispatch(&address, init_code);
+        assert!(
+            contract.is_some() || init_code.is_empty(),
+            "create is unsupported without the runner feature, unless a contract is registered at \
+             the new address {:?}",
+            address
+        );
+
+        let nonce = nonce.checked_add(1).ok_or(ExecutionOutcome::Aborted)?;
+        self.write_nonce(&creator, nonce);
+        if self.nonce(&address) != 0 || !self.code(&address).is_empty() {
+            return Err(ExecutionOutcome::Aborted);
+        }
+
+        // Forward all but one 64th of the gas left, as specified by EIP-150.
+        let gas = self.message.gas - self.message.gas / 64;
+        self.message.gas -= gas;
+        let message = Message {
+            caller: creator,
+            address,
+            value: *value,
+            data: Vec::new(),
+            gas,
+        };
+        self.push_frame(message, Context::default());
+
+        self.write_nonce(&address, 1);
+        if !self.transfer(&creator, &address, value) {
+            self.leave(&ExecutionOutcome::Aborted);
+            return Err(ExecutionOutcome::Aborted);
+        }
+        Ok((address, contract))
+    }
+
+    /// Returns from the init code of a new contract to its creator. If the init code finished, the
+    /// data it finished with is deployed as the code of the contract.
+    pub(crate) fn leave_create(&mut self, address: &Address, outcome: &ExecutionOutcome) {
+        if let ExecutionOutcome::Finished(code) = outcome {
+            self.write_code(address, code);
+        }
+        self.leave(outcome);
+        if outcome.is_success() {
+            self.return_data.clear();
+        }
+    }
+
+    pub(crate) fn finish(&mut self, data: &[u8]) -> Halt {
+        Halt::Finish(data.to_vec())
+    }
+
+    pub(crate) fn revert(&mut self, data: &[u8]) -> Halt {
+        Halt::Revert(data.to_vec())
+    }
+
+    /// Sends the whole balance of the executing account to `beneficiary` and deletes it.
+    pub(crate) fn selfdestruct(&mut self, beneficiary: &Address) -> Halt {
+        self.check_static("selfdestruct");
+        self.charge(self.schedule.selfdestruct);
+        let address = self.message.address;
+        let balance = self.balance(&address);
+        self.transfer(&address, beneficiary, &balance);
+        if let Some(account) = self.accounts.remove(&address) {
+            self.journal.record(Entry::Destroyed(address, account));
+        }
+        Halt::SelfDestruct(*beneficiary)
+    }
+
+    /// Moves `value` from `from` to `to`. Returns false if the balance of `from` is insufficient.
+    fn transfer(&mut self, from: &Address, to: &Address, value: &EtherValue) -> bool {
+        let from_balance = match self.balance(from).checked_sub(*value) {
+            Some(balance) => balance,
+            None => return false,
+        };
+        if from == to {
+            return true;
+        }
+        let to_balance = match self.balance(to).checked_add(*value) {
+            Some(balance) => balance,
+            None => return false,
+        };
+
+        self.write_balance(from, from_balance);
+        self.write_balance(to, to_balance);
+        true
+    }
+}
+
+/// Returns the address of the contract created by `creator` with the given nonce, which is the
+/// last 20 bytes of the Keccak-256 hash of the RLP encoding of `[creator, nonce]`.
+fn create_address(creator: &Address, nonce: u64) -> Address {
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+
+    let mut rlp = Vec::with_capacity(31);
+    rlp.push(0);
+    rlp.push(0x80 + 20);
+    rlp.extend_from_slice(&creator.bytes);
+    match nonce_bytes {
+        [byte] if *byte < 0x80 => rlp.push(*byte),
+        _ => {
+            rlp.push(0x80 + nonce_bytes.len() as u8);
+            rlp.extend_from_slice(nonce_bytes);
+        }
+    }
+    rlp[0] = 0xc0 + (rlp.len() - 1) as u8;
+
+    let mut address = Address::default();
+    address
+        .bytes
+        .copy_from_slice(&crate::crypto::keccak256(&rlp).bytes[12..]);
+    address
+}
@@ -0,0 +1,131 @@
+//! Recording changes to the world state, so that they can be undone when a call fails.
+
+use std::collections::BTreeMap;
+use std::vec::Vec;
+
+use super::host::Account;
+use super::logs::Log;
+use crate::types::*;
+
+/// A change to the world state, holding what is needed to undo it.
+#[derive(Clone, Debug)]
+pub(crate) enum Entry {
+    /// An account was created.
+    Created(Address),
+    /// An account was deleted.
+    Destroyed(Address, Account),
+    /// The balance of an account was changed from the given value.
+    Balance(Address, EtherValue),
+    /// The nonce of an account was changed from the given value.
+    Nonce(Address, u64),
+    /// The code of an account was changed from the given code.
+    Code(Address, Vec<u8>),
+    /// A storage slot was changed from the given value.
+    Storage(Address, StorageKey, StorageValue),
+    /// A log was appended.
+    Log,
+}
+
+/// The changes made to the world state, in order.
+#[derive(Default, Clone, Debug)]
+pub(crate) struct Journal {
+    entries: Vec<Entry>,
+}
+
+impl Journal {
+    /// Returns a checkpoint which the state can later be reverted to.
+    pub(crate) fn checkpoint(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn record(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    /// Undoes every change made since `checkpoint`, newest first.
+    pub(crate) fn revert(
+        &mut self,
+        checkpoint: usize,
+        accounts: &mut BTreeMap<Address, Account>,
+        logs: &mut Vec<Log>,
+    ) {
+        while self.entries.len() > checkpoint {
+            match self.entries.pop().expect("journal entry") {
+                Entry::Created(address) => {
+                    accounts.remove(&address);
+                }
+                Entry::Destroyed(address, account) => {
+                    accounts.insert(address, account);
+                }
+                Entry::Balance(address, balance) => {
+                    accounts.entry(address).or_default().balance = balance;
+                }
+                Entry::Nonce(address, nonce) => {
+                    accounts.entry(address).or_default().nonce = nonce;
+                }
+                Entry::Code(address, code) => {
+                    accounts.entry(address).or_default().code = code;
+                }
+                Entry::Storage(address, key, value) => {
+                    let storage = &mut accounts.entry(address).or_default().storage;
+                    if value == StorageValue::default() {
+                        storage.remove(&key);
+                    } else {
+                        storage.insert(key, value);
+                    }
+                }
+                Entry::Log => {
+                    logs.pop();
+                }
+            }
+        }
+    }
+
+    /// Forgets every recorded change, making them permanent.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::support;
+
+    #[test]
+    fn revert_to_checkpoint() {
+        let address = support::address(1);
+        let key = StorageKey::from([2; 32]);
+        let mut accounts = BTreeMap::new();
+        let mut logs = vec![Log::default()];
+        let mut journal = Journal::default();
+
+        journal.record(Entry::Created(address));
+        journal.record(Entry::Storage(address, key, StorageValue::default()));
+        accounts
+            .entry(address)
+            .or_insert_with(Account::default)
+            .storage
+            .insert(key, StorageValue::from([3; 32]));
+        let checkpoint = journal.checkpoint();
+
+        journal.record(Entry::Storage(address, key, StorageValue::from([3; 32])));
+        journal.record(Entry::Balance(address, EtherValue::default()));
+        journal.record(Entry::Log);
+        let account = accounts.get_mut(&address).unwrap();
+        account.storage.insert(key, StorageValue::from([4; 32]));
+        account.balance = EtherValue::from([5; 16]);
+        logs.push(Log::default());
+
+        journal.revert(checkpoint, &mut accounts, &mut logs);
+        assert_eq!(
+            accounts[&address].storage[&key],
+            StorageValue::from([3; 32])
+        );
+        assert_eq!(accounts[&address].balance, EtherValue::default());
+        assert_eq!(logs.len(), 1);
+
+        journal.revert(0, &mut accounts, &mut logs);
+        assert!(accounts.is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+//! Logs recorded by the test host, and assertions on them.
+
+use std::vec::Vec;
+
+use crate::types::*;
+
+/// A log entry appended with one of the `log` functions.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct Log {
+    /// The address of the contract which appended the log.
+    pub address: Address,
+    pub topics: Vec<LogTopic>,
+    pub data: Vec<u8>,
+}
+
+impl Log {
+    /// Returns true if the log has exactly the given topics and data.
+    pub fn matches(&self, topics: &[LogTopic], data: &[u8]) -> bool {
+        self.topics == topics && self.data == data
+    }
+}
+
+/// Returns the first log in `logs` with exactly the given topics and data.
+pub fn find_log<'a>(logs: &'a [Log], topics: &[LogTopic], data: &[u8]) -> Option<&'a Log> {
+    logs.iter().find(|log| log.matches(topics, data))
+}
+
+/// Asserts that a log with exactly the given topics and data is in `logs`, and returns it.
+///
+/// # Panics
+/// Panics listing every log if there is no such log.
+#[track_caller]
+pub fn assert_logged<'a>(logs: &'a [Log], topics: &[LogTopic], data: &[u8]) -> &'a Log {
+    match find_log(logs, topics, data) {
+        Some(log) => log,
+        None => panic!(
+            "no log with topics {:?} and data {:?} was appended; logs: {:#?}",
+            topics, data, logs
+        ),
+    }
+}
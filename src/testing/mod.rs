@@ -0,0 +1,563 @@
+//! An in-process implementation of the EEI, used to unit-test contracts natively with
+//! `cargo test` instead of compiling them to wasm.
+//!
+//! When the `testing` feature is enabled on a non-wasm target, every `ethereum_*` function used by
+//! the safe wrappers is implemented in Rust and operates on the [`Host`] of the current thread.
+//! Each test runs on its own thread, so tests are isolated from each other and can run in
+//! parallel.
+//!
+//! Since `finish`, `revert` and `selfdestruct` halt the contract by unwinding the thread, entry
+//! functions should be run with [`execute`], which reports how the execution terminated.
+//!
+//! Other contracts can be implemented in Rust and registered at an address with
+//! [`Host::register_contract`]. Calls made with `call_mutable`, `call_static`, `call_delegate`
+//! and `call_code` are then dispatched to them, with the context of the nested call set up as a
+//! VM would. Changes to the state are journaled, so that the changes made by a failed call are
+//! rolled back while its caller continues. Changing the state in a static call aborts it.
+//!
+//! Contracts created with `create` get the address derived from their creator and its nonce. Their
+//! init code is run as a nested call to the contract registered at that address, or with the
+//! `runner` feature, as a wasm module, and the data it finishes with is deployed as their code.
+//!
+//! Every EEI function charges gas from the current call according to the host's [`GasSchedule`],
+//! and nested calls are given the gas limit passed to them. When the gas runs out, the call halts
+//! with [`ExecutionOutcome::OutOfGas`].
+//!
+//! Logs are recorded in order with the address of the contract appending them, and are discarded
+//! when the call appending them fails. They can be checked with [`Execution::assert_logged`].
+//!
+//! With the `fixtures` feature, state tests can be loaded from JSON fixtures and run with the
+//! [`fixture`] module.
+//!
+//! # Examples
+//! ```
+//! use ewasm_api::prelude::*;
+//! use ewasm_api::testing::{self, Host};
+//!
+//! fn entry() {
+//!     let key = StorageKey::default();
+//!     let mut value = storage_load(&key);
+//!     value.bytes[0] += 1;
+//!     storage_store(&key, &value);
+//! }
+//!
+//! let contract = Address::from([0x11; 20]);
+//! let mut host = Host::new();
+//! host.message.address = contract;
+//! testing::set_host(host);
+//!
+//! entry();
+//! entry();
+//!
+//! let host = testing::take_host();
+//! assert_eq!(host.storage(&contract, &StorageKey::default()).bytes[0], 2);
+//! ```
+
+use std::cell::RefCell;
+
+pub(crate) mod eei;
+mod execution;
+#[cfg(feature = "fixtures")]
+pub mod fixture;
+mod gas;
+mod host;
+mod journal;
+mod logs;
+#[cfg(test)]
+pub(crate) mod support;
+
+pub use self::execution::*;
+pub use self::gas::*;
+pub use self::host::*;
+pub use self::logs::*;
+
+thread_local! {
+    static HOST: RefCell<Host> = RefCell::new(Host::new());
+}
+
+/// Replaces the host of the current thread, returning the previous one.
+pub fn set_host(host: Host) -> Host {
+    HOST.with(|current| current.replace(host))
+}
+
+/// Takes the host of the current thread, leaving an empty one in its place.
+pub fn take_host() -> Host {
+    set_host(Host::new())
+}
+
+/// Runs `f` with the host of the current thread.
+pub fn with_host<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut Host) -> R,
+{
+    HOST.with(|current| f(&mut current.borrow_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::support::{address, setup};
+    use super::*;
+    use crate::*;
+
+    const GAS: u64 = 1_000_000;
+
+    fn value(amount: u128) -> EtherValue {
+        EtherValue::from(amount)
+    }
+
+    #[test]
+    fn context() {
+        let mut host = support::host();
+        host.message.caller = address(2);
+        host.message.value = value(3);
+        host.tx.origin = address(4);
+        host.block.number = 5;
+        host.block.hashes.insert(4, Hash::from([6; 32]));
+        set_host(host);
+
+        assert_eq!(current_address(), address(1));
+        assert_eq!(caller(), address(2));
+        assert_eq!(callvalue(), value(3));
+        assert_eq!(tx_origin(), address(4));
+        assert_eq!(block_number(), 5);
+        assert_eq!(block_hash(4), Hash::from([6; 32]));
+        assert_eq!(block_hash(3), Hash::default());
+    }
+
+    #[test]
+    fn calldata() {
+        let mut host = Host::new();
+        host.message.data = vec![1, 2, 3, 4];
+        set_host(host);
+
+        assert_eq!(calldata_size(), 4);
+        assert_eq!(calldata_acquire(), vec![1, 2, 3, 4]);
+
+        let mut buf = [0u8; 2];
+        assert!(calldata_copy(1, 2, &mut buf).is_ok());
+        assert_eq!(buf, [2, 3]);
+        assert!(calldata_copy(3, 2, &mut buf).is_err());
+    }
+
+    #[test]
+    fn deployer() {
+        let mut host = support::host();
+        host.message.data = vec![7];
+        set_host(host);
+
+        let runtime = b"\0asm\x01\0\0\0";
+        let execution = execute(|| {
+            __private::deploy(runtime, || {
+                let value = StorageValue::from([calldata_acquire()[0]; 32]);
+                storage_store(&StorageKey::default(), &value);
+            })
+        });
+        assert_eq!(
+            execution.outcome,
+            ExecutionOutcome::Finished(runtime.to_vec())
+        );
+        assert_eq!(
+            take_host().storage(&address(1), &StorageKey::default()),
+            StorageValue::from([7; 32])
+        );
+    }
+
+    #[test]
+    fn storage() {
+        let key = StorageKey::from([1; 32]);
+        let mut host = support::host();
+        host.set_storage(&address(1), &key, &StorageValue::from([2; 32]));
+        set_host(host);
+
+        assert_eq!(storage_load(&key), StorageValue::from([2; 32]));
+        storage_store(&key, &StorageValue::from([3; 32]));
+        storage_store(&StorageKey::default(), &StorageValue::from([4; 32]));
+
+        let host = take_host();
+        assert_eq!(host.storage(&address(1), &key), StorageValue::from([3; 32]));
+        assert_eq!(
+            host.storage(&address(1), &StorageKey::default()),
+            StorageValue::from([4; 32])
+        );
+    }
+
+    #[test]
+    fn code() {
+        let mut host = support::host();
+        host.set_code(&address(1), &[0, 0x61, 0x73, 0x6d]);
+        host.set_code(&address(2), &[1, 2]);
+        set_host(host);
+
+        assert_eq!(code_acquire(), vec![0, 0x61, 0x73, 0x6d]);
+        assert_eq!(external_code_size(&address(2)), 2);
+        assert_eq!(external_code_acquire(&address(2)), vec![1, 2]);
+        assert_eq!(external_code_size(&address(3)), 0);
+    }
+
+    #[test]
+    fn value_transfer() {
+        setup();
+
+        assert!(matches!(
+            call_mutable(GAS, &address(2), &value(60), &[]),
+            CallResult::Successful
+        ));
+        assert!(matches!(
+            call_mutable(GAS, &address(2), &value(60), &[]),
+            CallResult::Failure
+        ));
+        assert_eq!(external_balance(&address(1)), value(40));
+        assert_eq!(external_balance(&address(2)), value(60));
+    }
+
+    #[test]
+    fn logs() {
+        setup();
+
+        log0(&[1]);
+        log2(&[2], &LogTopic::from([3; 32]), &LogTopic::from([4; 32]));
+
+        let host = take_host();
+        assert_eq!(
+            host.logs(),
+            &[
+                Log {
+                    address: address(1),
+                    topics: vec![],
+                    data: vec![1],
+                },
+                Log {
+                    address: address(1),
+                    topics: vec![LogTopic::from([3; 32]), LogTopic::from([4; 32])],
+                    data: vec![2],
+                },
+            ]
+        );
+    }
+
+    fn result_code(result: CallResult) -> u32 {
+        match result {
+            CallResult::Successful => 0,
+            CallResult::Failure => 1,
+            CallResult::Revert => 2,
+            CallResult::Unknown => 3,
+        }
+    }
+
+    /// Echoes its context, and stores the calldata under a key given by its first byte.
+    fn echo() {
+        let data = calldata_acquire();
+        if data.first() == Some(&0xff) {
+            revert_data(b"rejected");
+        }
+        storage_store(
+            &StorageKey::from([data[0]; 32]),
+            &StorageValue::from([1; 32]),
+        );
+
+        let mut ret = Vec::new();
+        ret.extend_from_slice(&current_address().bytes);
+        ret.extend_from_slice(&caller().bytes);
+        ret.extend_from_slice(&callvalue().bytes);
+        finish_data(&ret)
+    }
+
+    fn setup_calls() {
+        let mut host = support::host();
+        host.message.caller = address(9);
+        host.message.value = value(7);
+        host.register_contract(&address(2), echo);
+        set_host(host);
+    }
+
+    fn echoed(address: Address, caller: Address, value: EtherValue) -> Vec<u8> {
+        let mut ret = address.bytes.to_vec();
+        ret.extend_from_slice(&caller.bytes);
+        ret.extend_from_slice(&value.bytes);
+        ret
+    }
+
+    #[test]
+    fn call_registered_contract() {
+        setup_calls();
+
+        assert_eq!(
+            result_code(call_mutable(GAS, &address(2), &value(10), &[3])),
+            0
+        );
+        assert_eq!(
+            returndata_acquire(),
+            echoed(address(2), address(1), value(10))
+        );
+        assert_eq!(current_address(), address(1));
+        assert_eq!(caller(), address(9));
+
+        let host = take_host();
+        assert_eq!(host.balance(&address(2)), value(10));
+        assert_eq!(
+            host.storage(&address(2), &StorageKey::from([3; 32])),
+            StorageValue::from([1; 32])
+        );
+    }
+
+    #[test]
+    fn call_reverted() {
+        setup_calls();
+
+        assert_eq!(
+            result_code(call_mutable(GAS, &address(2), &value(10), &[0xff])),
+            2
+        );
+        assert_eq!(returndata_acquire(), b"rejected".to_vec());
+
+        let host = take_host();
+        assert_eq!(host.balance(&address(1)), value(100));
+        assert_eq!(host.balance(&address(2)), value(0));
+    }
+
+    #[test]
+    fn call_delegate_and_code() {
+        setup_calls();
+
+        assert_eq!(result_code(call_delegate(GAS, &address(2), &[3])), 0);
+        assert_eq!(
+            returndata_acquire(),
+            echoed(address(1), address(9), value(7))
+        );
+
+        assert_eq!(result_code(call_code(GAS, &address(2), &value(5), &[4])), 0);
+        assert_eq!(
+            returndata_acquire(),
+            echoed(address(1), address(1), value(5))
+        );
+
+        let host = take_host();
+        assert_eq!(host.balance(&address(1)), value(100));
+        assert_eq!(
+            host.storage(&address(1), &StorageKey::from([3; 32])),
+            StorageValue::from([1; 32])
+        );
+        assert_eq!(
+            host.storage(&address(1), &StorageKey::from([4; 32])),
+            StorageValue::from([1; 32])
+        );
+    }
+
+    #[test]
+    fn call_static_context() {
+        setup_calls();
+        with_host(|host| host.register_contract(&address(3), || finish_data(&[42])));
+
+        assert_eq!(result_code(call_static(GAS, &address(3), &[])), 0);
+        assert_eq!(returndata_acquire(), vec![42]);
+    }
+
+    #[test]
+    fn nested_calls() {
+        setup_calls();
+        with_host(|host| {
+            host.register_contract(&address(3), || {
+                call_mutable(GAS, &address(2), &EtherValue::default(), &[5]);
+                let ret = returndata_acquire();
+                finish_data(&ret)
+            })
+        });
+
+        assert_eq!(
+            result_code(call_mutable(GAS, &address(3), &EtherValue::default(), &[])),
+            0
+        );
+        assert_eq!(
+            returndata_acquire(),
+            echoed(address(2), address(3), value(0))
+        );
+    }
+
+    #[test]
+    fn nested_revert() {
+        setup_calls();
+        with_host(|host| {
+            host.register_contract(&address(3), || {
+                storage_store(&StorageKey::default(), &StorageValue::from([1; 32]));
+                log0(&[3]);
+                call_mutable(GAS, &address(2), &value(10), &[5]);
+                revert()
+            })
+        });
+
+        let execution = execute(|| {
+            log0(&[1]);
+            assert_eq!(
+                result_code(call_mutable(GAS, &address(3), &value(50), &[])),
+                2
+            );
+            assert_eq!(
+                result_code(call_mutable(GAS, &address(2), &value(20), &[6])),
+                0
+            );
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+
+        let host = take_host();
+        assert_eq!(host.balance(&address(1)), value(80));
+        assert_eq!(host.balance(&address(2)), value(20));
+        assert!(!host.accounts.contains_key(&address(3)));
+        assert_eq!(
+            host.storage(&address(2), &StorageKey::from([5; 32])),
+            StorageValue::default()
+        );
+        assert_eq!(
+            host.storage(&address(2), &StorageKey::from([6; 32])),
+            StorageValue::from([1; 32])
+        );
+        assert_eq!(host.logs().len(), 1);
+    }
+
+    #[test]
+    fn static_violation() {
+        setup_calls();
+
+        assert_eq!(result_code(call_static(GAS, &address(2), &[3])), 1);
+        assert!(returndata_acquire().is_empty());
+
+        with_host(|host| {
+            host.register_contract(&address(3), || {
+                call_mutable(GAS, &address(2), &EtherValue::default(), &[3]);
+            })
+        });
+        assert_eq!(result_code(call_static(GAS, &address(3), &[])), 0);
+        assert_eq!(
+            take_host().storage(&address(2), &StorageKey::from([3; 32])),
+            StorageValue::default()
+        );
+    }
+
+    fn created(result: CreateResult) -> Option<Address> {
+        match result {
+            CreateResult::Successful(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn create_contract() {
+        let creator = address!("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        let first = address!("0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d");
+        let second = address!("0x343c43a37d37dff08ae8c4a11544c718abb4fcf8");
+        let mut host = Host::new();
+        host.message.address = creator;
+        host.set_balance(&creator, value(100));
+        host.register_contract(&first, || {
+            __private::deploy(&[1, 2, 3], || {
+                let key = StorageKey::from([caller().bytes[0]; 32]);
+                storage_store(&key, &StorageValue::from([1; 32]));
+            })
+        });
+        host.register_contract(&second, || revert_data(b"rejected"));
+        set_host(host);
+
+        assert_eq!(created(crate::create(&value(10), &[0])), Some(first));
+        assert!(returndata_acquire().is_empty());
+        assert!(created(crate::create(&value(10), &[0])).is_none());
+        assert_eq!(returndata_acquire(), b"rejected".to_vec());
+
+        let host = take_host();
+        assert_eq!(host.nonce(&creator), 2);
+        assert_eq!(host.balance(&creator), value(90));
+        assert_eq!(host.nonce(&first), 1);
+        assert_eq!(host.code(&first), [1, 2, 3]);
+        assert_eq!(host.balance(&first), value(10));
+        assert_eq!(
+            host.storage(&first, &StorageKey::from([0x6a; 32])),
+            StorageValue::from([1; 32])
+        );
+        assert!(!host.accounts.contains_key(&second));
+    }
+
+    #[test]
+    #[cfg(not(feature = "runner"))]
+    fn create_unsupported() {
+        set_host(Host::new());
+        let execution = execute(|| {
+            crate::create(&EtherValue::default(), &[0]);
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Aborted);
+    }
+
+    #[test]
+    fn gas() {
+        let mut host = support::host();
+        host.message.gas = 100_000;
+        host.register_contract(&address(2), || {
+            storage_store(&StorageKey::default(), &StorageValue::from([1; 32]));
+        });
+        host.register_contract(&address(3), || consume_gas(10));
+        set_host(host);
+
+        let execution = execute(|| {
+            consume_gas(30);
+            assert_eq!(gas_left(), 100_000 - 32);
+            assert_eq!(
+                result_code(call_mutable(10_000, &address(2), &value(0), &[])),
+                1
+            );
+            assert_eq!(gas_left(), 100_000 - 32 - 700 - 10_000 - 2);
+            assert_eq!(result_code(call_static(100, &address(3), &[])), 0);
+            assert_eq!(gas_left(), 100_000 - 32 - 700 - 10_000 - 2 - 700 - 10 - 2);
+            storage_store(&StorageKey::default(), &StorageValue::from([1; 32]));
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::Finished(vec![]));
+        assert_eq!(
+            execution.gas_used,
+            32 + 700 + 10_000 + 2 + 700 + 10 + 2 + 20_000
+        );
+
+        with_host(|host| host.message.gas = 1000);
+        let execution = execute(|| {
+            storage_store(&StorageKey::default(), &StorageValue::default());
+        });
+        assert_eq!(execution.outcome, ExecutionOutcome::OutOfGas);
+        assert_eq!(execution.gas_used, 1000);
+        assert!(execution.diff.is_empty());
+    }
+
+    #[test]
+    fn logs_of_execution() {
+        setup_calls();
+        with_host(|host| {
+            host.register_contract(&address(3), || {
+                log1(&[3], &LogTopic::from([3; 32]));
+                revert()
+            });
+            host.register_contract(&address(4), || {
+                log1(&[4], &LogTopic::from([4; 32]));
+            });
+        });
+
+        let execution = execute(|| {
+            log0(&[1]);
+            call_mutable(GAS, &address(3), &value(0), &[]);
+            call_mutable(GAS, &address(4), &value(0), &[]);
+            log2(&[2], &LogTopic::from([1; 32]), &LogTopic::from([2; 32]));
+        });
+
+        assert_eq!(execution.logs.len(), 3);
+        assert_eq!(execution.assert_logged(&[], &[1]).address, address(1));
+        assert_eq!(
+            execution
+                .assert_logged(&[LogTopic::from([4; 32])], &[4])
+                .address,
+            address(4)
+        );
+        execution.assert_logged(&[LogTopic::from([1; 32]), LogTopic::from([2; 32])], &[2]);
+        assert!(find_log(&execution.logs, &[LogTopic::from([3; 32])], &[3]).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "no log with topics")]
+    fn missing_log() {
+        setup_calls();
+        let execution = execute(|| log0(&[1]));
+        execution.assert_logged(&[], &[2]);
+    }
+}
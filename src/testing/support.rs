@@ -0,0 +1,22 @@
+//! Helpers shared by the tests of the host and the runner.
+
+use super::{set_host, Host};
+use crate::types::{Address, EtherValue};
+
+/// The address with all bytes set to `byte`.
+pub(crate) fn address(byte: u8) -> Address {
+    Address::from([byte; 20])
+}
+
+/// Creates a host executing as `address(1)`, which holds 100 wei.
+pub(crate) fn host() -> Host {
+    let mut host = Host::new();
+    host.message.address = address(1);
+    host.set_balance(&address(1), EtherValue::from(100u128));
+    host
+}
+
+/// Replaces the host of the current thread with `host()`.
+pub(crate) fn setup() {
+    set_host(host());
+}
@@ -1,46 +1,97 @@
 //! High-level types commonly used in Ethereum contracts.
 
-/// A little-endian unsigned 128-bit integer.
-#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+/// An unsigned 128-bit integer, stored little-endian: `bytes[0]` is the least significant byte.
+/// Convert from and to native integers with `From` and `TryFrom`, and from and to big-endian data
+/// with `from_be_bytes` and `to_be_bytes`.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Uint128 {
     pub bytes: [u8; 16],
 }
 
-/// A little-endian unsigned 256-bit integer.
-#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+/// An unsigned 256-bit integer, stored little-endian: `bytes[0]` is the least significant byte.
+/// Convert from and to native integers with `From` and `TryFrom`, and from and to big-endian data
+/// with `from_be_bytes` and `to_be_bytes`.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Uint256 {
     pub bytes: [u8; 32],
 }
 
-/// An array of 160 bits.
-#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
-pub struct Bytes20 {
-    pub bytes: [u8; 20],
+/// A little-endian signed 256-bit integer in two's complement, following the semantics of the
+/// EVM and of Solidity's `int256`.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Int256 {
+    pub bytes: [u8; 32],
 }
 
-/// An array of 256 bits.
-#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
-pub struct Bytes32 {
-    pub bytes: [u8; 32],
+/// An array of `N` bytes, such as the `bytesN` types of the ABI. Compares lexicographically.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedBytes<const N: usize> {
+    pub bytes: [u8; N],
 }
 
-/// Type definition representing a value in wei.
-pub type EtherValue = Uint128;
+/// An array of 160 bits.
+pub type Bytes20 = FixedBytes<20>;
+
+/// An array of 256 bits.
+pub type Bytes32 = FixedBytes<32>;
+
+cfg_if! {
+    if #[cfg(feature = "newtypes")] {
+        /// A value in wei, with the same API as `Uint128`.
+        #[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
+        pub struct EtherValue {
+            pub bytes: [u8; 16],
+        }
+
+        /// An address, with the same API as `Bytes20`.
+        #[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct Address {
+            pub bytes: [u8; 20],
+        }
+
+        /// A storage key, with the same API as `Bytes32`.
+        #[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct StorageKey {
+            pub bytes: [u8; 32],
+        }
+
+        /// A storage value, with the same API as `Bytes32`.
+        #[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct StorageValue {
+            pub bytes: [u8; 32],
+        }
+
+        /// A log topic, with the same API as `Bytes32`.
+        #[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct LogTopic {
+            pub bytes: [u8; 32],
+        }
+
+        /// A Keccak-256 or SHA-256 hash, with the same API as `Bytes32`.
+        #[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct Hash {
+            pub bytes: [u8; 32],
+        }
+    } else {
+        /// Type definition representing a value in wei.
+        pub type EtherValue = Uint128;
 
-/// Type definition representing an address.
-pub type Address = Bytes20;
+        /// Type definition representing an address.
+        pub type Address = Bytes20;
 
-/// Type definition representing a storage key.
-pub type StorageKey = Bytes32;
+        /// Type definition representing a storage key.
+        pub type StorageKey = Bytes32;
 
-/// Type definition representing a storage value.
-pub type StorageValue = Bytes32;
+        /// Type definition representing a storage value.
+        pub type StorageValue = Bytes32;
 
-/// Type definition representing a log topic.
-pub type LogTopic = Bytes32;
+        /// Type definition representing a log topic.
+        pub type LogTopic = Bytes32;
 
-/// Type definition representing a Keccak-256 or SHA-256 hash.
-pub type Hash = Bytes32;
+        /// Type definition representing a Keccak-256 or SHA-256 hash.
+        pub type Hash = Bytes32;
+    }
+}
 
 /// Type definition representing a block's difficulty.
 pub type Difficulty = Uint256;
@@ -77,47 +128,2309 @@ macro_rules! from_type_for_primitive_impl {
 
 from_primitive_impl!(u8, 16, Uint128);
 from_primitive_impl!(u8, 32, Uint256);
-from_primitive_impl!(u8, 20, Bytes20);
-from_primitive_impl!(u8, 32, Bytes32);
+from_primitive_impl!(u8, 32, Int256);
 
 from_primitive_ref_impl!(u8, 16, Uint128);
 from_primitive_ref_impl!(u8, 32, Uint256);
-from_primitive_ref_impl!(u8, 20, Bytes20);
-from_primitive_ref_impl!(u8, 32, Bytes32);
+from_primitive_ref_impl!(u8, 32, Int256);
 
 from_type_for_primitive_impl!(Uint128, 16, u8);
 from_type_for_primitive_impl!(Uint256, 32, u8);
-from_type_for_primitive_impl!(Bytes20, 20, u8);
-from_type_for_primitive_impl!(Bytes32, 32, u8);
+from_type_for_primitive_impl!(Int256, 32, u8);
 
-#[cfg(test)]
-mod tests {
-    use super::{Bytes20, Bytes32, Uint128, Uint256};
+impl<const N: usize> Default for FixedBytes<N> {
+    fn default() -> Self {
+        FixedBytes { bytes: [0; N] }
+    }
+}
 
-    macro_rules! test_conversions {
-        ($type: ident, $size: expr, $test_name: ident) => {
-            #[test]
-            fn $test_name() {
-                let raw = [1; $size];
+impl<const N: usize> From<[u8; N]> for FixedBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        FixedBytes { bytes }
+    }
+}
 
-                let uint = $type::from(raw);
-                assert_eq!(uint.bytes[$size - 1], 1);
-                let uint = $type::from(&raw);
-                assert_eq!(uint.bytes[$size - 1], 1);
+impl<const N: usize> From<&[u8; N]> for FixedBytes<N> {
+    fn from(bytes: &[u8; N]) -> Self {
+        FixedBytes { bytes: *bytes }
+    }
+}
 
-                let uint: $type = raw.into();
-                assert_eq!(uint.bytes[$size - 1], 1);
-                let uint: $type = (&raw).into();
-                assert_eq!(uint.bytes[$size - 1], 1);
+impl<const N: usize> From<FixedBytes<N>> for [u8; N] {
+    fn from(value: FixedBytes<N>) -> Self {
+        value.bytes
+    }
+}
 
-                let r: [u8; $size] = uint.into();
-                assert_eq!(r[$size - 1], 1);
+/// Implements the slice-like API of an array of `$size` bytes: `LEN`, `as_slice`, `AsRef`,
+/// `AsMut`, indexing and `TryFrom<&[u8]>`.
+macro_rules! bytes_impl {
+    ($type:ident, $size:expr) => {
+        bytes_impl!([] $type, $size);
+    };
+    ([$($generics:tt)*] $type:ty, $size:expr) => {
+        /// Fails if the slice is not exactly `LEN` bytes long.
+        impl<$($generics)*> core::convert::TryFrom<&[u8]> for $type {
+            type Error = core::array::TryFromSliceError;
+
+            fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+                <[u8; $size]>::try_from(bytes).map(Self::from)
             }
-        };
+        }
+
+        impl<$($generics)*> $type {
+            /// The number of bytes.
+            pub const LEN: usize = $size;
+
+            /// Returns the bytes as a slice.
+            pub fn as_slice(&self) -> &[u8] {
+                &self.bytes
+            }
+        }
+
+        impl<$($generics)*> AsRef<[u8]> for $type {
+            fn as_ref(&self) -> &[u8] {
+                &self.bytes
+            }
+        }
+
+        impl<$($generics)*> AsMut<[u8]> for $type {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.bytes
+            }
+        }
+
+        /// Indexes the bytes like a slice, so that `hash[..4]` is the first four bytes.
+        impl<I: core::slice::SliceIndex<[u8]>, $($generics)*> core::ops::Index<I> for $type {
+            type Output = I::Output;
+
+            fn index(&self, index: I) -> &I::Output {
+                &self.bytes[index]
+            }
+        }
+
+        impl<I: core::slice::SliceIndex<[u8]>, $($generics)*> core::ops::IndexMut<I> for $type {
+            fn index_mut(&mut self, index: I) -> &mut I::Output {
+                &mut self.bytes[index]
+            }
+        }
+    };
+}
+
+bytes_impl!([const N: usize] FixedBytes<N>, N);
+
+/// Implements the arithmetic of a 128-bit type through `u128`.
+macro_rules! u128_arithmetic_impl {
+    ($type:ident) => {
+        impl $type {
+            /// Calculates `self + rhs`, returning whether an overflow occurred.
+            pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+                let (ret, overflow) = u128::from(self).overflowing_add(u128::from(rhs));
+                (Self::from(ret), overflow)
+            }
+
+            /// Calculates `self - rhs`, returning whether an overflow occurred.
+            pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+                let (ret, overflow) = u128::from(self).overflowing_sub(u128::from(rhs));
+                (Self::from(ret), overflow)
+            }
+
+            /// Calculates `self * rhs`, returning whether an overflow occurred.
+            pub fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+                let (ret, overflow) = u128::from(self).overflowing_mul(u128::from(rhs));
+                (Self::from(ret), overflow)
+            }
+
+            /// Returns the quotient and remainder of `self / rhs`, or `None` if `rhs` is zero.
+            fn div_rem(self, rhs: Self) -> Option<(Self, Self)> {
+                let (a, b) = (u128::from(self), u128::from(rhs));
+                if b == 0 {
+                    return None;
+                }
+                Some((Self::from(a / b), Self::from(a % b)))
+            }
+        }
+
+        impl From<$type> for u128 {
+            fn from(value: $type) -> Self {
+                u128::from_le_bytes(value.bytes)
+            }
+        }
+    };
+}
+
+u128_arithmetic_impl!(Uint128);
+
+/// The number of 64-bit limbs in a `Uint256`.
+const LIMBS: usize = 4;
+
+impl Uint256 {
+    /// Returns the 64-bit limbs of the number, least significant first.
+    fn limbs(self) -> [u64; LIMBS] {
+        let mut ret = [0u64; LIMBS];
+        for (limb, chunk) in ret.iter_mut().zip(self.bytes.chunks(8)) {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(buf);
+        }
+        ret
     }
 
-    test_conversions!(Uint128, 16, test_uint128);
-    test_conversions!(Uint256, 32, test_uint256);
-    test_conversions!(Bytes20, 20, test_bytes20);
-    test_conversions!(Bytes32, 32, test_bytes32);
+    fn from_limbs(limbs: &[u64; LIMBS]) -> Self {
+        let mut ret = Self::default();
+        for (chunk, limb) in ret.bytes.chunks_mut(8).zip(limbs.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        ret
+    }
+
+    /// Calculates `self + rhs`, returning whether an overflow occurred.
+    pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let (mut a, b) = (self.limbs(), rhs.limbs());
+        let carry = add_limbs(&mut a, &b);
+        (Self::from_limbs(&a), carry)
+    }
+
+    /// Calculates `self - rhs`, returning whether an overflow occurred.
+    pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let (mut a, b) = (self.limbs(), rhs.limbs());
+        let borrow = sub_limbs(&mut a, &b);
+        (Self::from_limbs(&a), borrow)
+    }
+
+    /// Calculates `self * rhs`, returning whether an overflow occurred.
+    pub fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+        let (a, b) = (self.limbs(), rhs.limbs());
+        let mut ret = [0u64; LIMBS];
+        let mut overflow = false;
+        for i in 0..LIMBS {
+            let mut carry = 0u128;
+            for j in 0..LIMBS {
+                let product = u128::from(a[i]) * u128::from(b[j]) + carry;
+                if i + j < LIMBS {
+                    let sum = product + u128::from(ret[i + j]);
+                    ret[i + j] = sum as u64;
+                    carry = sum >> 64;
+                } else {
+                    overflow |= product != 0;
+                    carry = product >> 64;
+                }
+            }
+            overflow |= carry != 0;
+        }
+        (Self::from_limbs(&ret), overflow)
+    }
+
+    /// Returns the quotient and remainder of `self / rhs`, or `None` if `rhs` is zero.
+    fn div_rem(self, rhs: Self) -> Option<(Self, Self)> {
+        let (a, b) = (self.limbs(), rhs.limbs());
+        if b == [0; LIMBS] {
+            return None;
+        }
+
+        // Binary long division. The remainder is always less than `b`, so when shifting it out
+        // of range it is also greater than `b`.
+        let mut quotient = [0u64; LIMBS];
+        let mut remainder = [0u64; LIMBS];
+        for bit in (0..LIMBS * 64).rev() {
+            let mut carry = (a[bit / 64] >> (bit % 64)) & 1;
+            for limb in remainder.iter_mut() {
+                let shifted_out = *limb >> 63;
+                *limb = (*limb << 1) | carry;
+                carry = shifted_out;
+            }
+            if carry != 0 || !less_limbs(&remainder, &b) {
+                sub_limbs(&mut remainder, &b);
+                quotient[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        Some((Self::from_limbs(&quotient), Self::from_limbs(&remainder)))
+    }
+}
+
+/// Adds `b` to `a` in place, returning the carry.
+fn add_limbs(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = false;
+    for (x, y) in a.iter_mut().zip(b) {
+        let (sum, c1) = x.overflowing_add(*y);
+        let (sum, c2) = sum.overflowing_add(carry as u64);
+        *x = sum;
+        carry = c1 || c2;
+    }
+    carry
+}
+
+/// Subtracts `b` from `a` in place, returning the borrow.
+fn sub_limbs(a: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = false;
+    for (x, y) in a.iter_mut().zip(b) {
+        let (diff, b1) = x.overflowing_sub(*y);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        *x = diff;
+        borrow = b1 || b2;
+    }
+    borrow
+}
+
+/// Returns true if `a` is less than `b`.
+fn less_limbs(a: &[u64], b: &[u64]) -> bool {
+    a.iter().rev().lt(b.iter().rev())
+}
+
+macro_rules! uint_arithmetic_impl {
+    ($type:ident, $size:expr) => {
+        impl $type {
+            /// The smallest value, zero.
+            pub const MIN: $type = $type { bytes: [0; $size] };
+            /// The largest value.
+            pub const MAX: $type = $type {
+                bytes: [0xff; $size],
+            };
+
+            /// Returns true if the value is zero.
+            pub fn is_zero(&self) -> bool {
+                self.bytes.iter().all(|byte| *byte == 0)
+            }
+
+            /// Calculates `self + rhs`, returning `None` on overflow.
+            pub fn checked_add(self, rhs: Self) -> Option<Self> {
+                match self.overflowing_add(rhs) {
+                    (ret, false) => Some(ret),
+                    _ => None,
+                }
+            }
+
+            /// Calculates `self - rhs`, returning `None` on overflow.
+            pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+                match self.overflowing_sub(rhs) {
+                    (ret, false) => Some(ret),
+                    _ => None,
+                }
+            }
+
+            /// Calculates `self * rhs`, returning `None` on overflow.
+            pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+                match self.overflowing_mul(rhs) {
+                    (ret, false) => Some(ret),
+                    _ => None,
+                }
+            }
+
+            /// Calculates `self / rhs`, returning `None` if `rhs` is zero.
+            pub fn checked_div(self, rhs: Self) -> Option<Self> {
+                self.div_rem(rhs).map(|(quotient, _)| quotient)
+            }
+
+            /// Calculates `self % rhs`, returning `None` if `rhs` is zero.
+            pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+                self.div_rem(rhs).map(|(_, remainder)| remainder)
+            }
+
+            /// Calculates `self + rhs`, wrapping around on overflow.
+            pub fn wrapping_add(self, rhs: Self) -> Self {
+                self.overflowing_add(rhs).0
+            }
+
+            /// Calculates `self - rhs`, wrapping around on overflow.
+            pub fn wrapping_sub(self, rhs: Self) -> Self {
+                self.overflowing_sub(rhs).0
+            }
+
+            /// Calculates `self * rhs`, wrapping around on overflow.
+            pub fn wrapping_mul(self, rhs: Self) -> Self {
+                self.overflowing_mul(rhs).0
+            }
+
+            /// Calculates `self / rhs`, which never overflows for unsigned integers.
+            ///
+            /// # Panics
+            /// Panics if `rhs` is zero.
+            pub fn wrapping_div(self, rhs: Self) -> Self {
+                self / rhs
+            }
+
+            /// Calculates `self % rhs`, which never overflows for unsigned integers.
+            ///
+            /// # Panics
+            /// Panics if `rhs` is zero.
+            pub fn wrapping_rem(self, rhs: Self) -> Self {
+                self % rhs
+            }
+
+            /// Calculates `self + rhs`, saturating at the maximum value.
+            pub fn saturating_add(self, rhs: Self) -> Self {
+                self.checked_add(rhs).unwrap_or(Self::MAX)
+            }
+
+            /// Calculates `self - rhs`, saturating at zero.
+            pub fn saturating_sub(self, rhs: Self) -> Self {
+                self.checked_sub(rhs).unwrap_or(Self::MIN)
+            }
+
+            /// Calculates `self * rhs`, saturating at the maximum value.
+            pub fn saturating_mul(self, rhs: Self) -> Self {
+                self.checked_mul(rhs).unwrap_or(Self::MAX)
+            }
+        }
+    };
+}
+
+/// Implements an arithmetic operator for `Uint128` and `Uint256`, panicking on overflow
+/// regardless of the build profile.
+macro_rules! uint_operator_impl {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $checked:ident, $message:expr) => {
+        uint_operator_impl!(
+            Uint128,
+            $trait,
+            $method,
+            $assign_trait,
+            $assign_method,
+            $checked,
+            $message
+        );
+        uint_operator_impl!(
+            Uint256,
+            $trait,
+            $method,
+            $assign_trait,
+            $assign_method,
+            $checked,
+            $message
+        );
+        #[cfg(feature = "newtypes")]
+        uint_operator_impl!(
+            EtherValue,
+            $trait,
+            $method,
+            $assign_trait,
+            $assign_method,
+            $checked,
+            $message
+        );
+    };
+    ($type:ident, $trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $checked:ident, $message:expr) => {
+        impl core::ops::$trait for $type {
+            type Output = $type;
+
+            fn $method(self, rhs: $type) -> $type {
+                self.$checked(rhs)
+                    .unwrap_or_else(|| panic!(concat!("attempt to ", $message)))
+            }
+        }
+
+        impl core::ops::$assign_trait for $type {
+            fn $assign_method(&mut self, rhs: $type) {
+                *self = core::ops::$trait::$method(*self, rhs);
+            }
+        }
+    };
+}
+
+uint_arithmetic_impl!(Uint128, 16);
+uint_arithmetic_impl!(Uint256, 32);
+
+uint_operator_impl!(
+    Add,
+    add,
+    AddAssign,
+    add_assign,
+    checked_add,
+    "add with overflow"
+);
+uint_operator_impl!(
+    Sub,
+    sub,
+    SubAssign,
+    sub_assign,
+    checked_sub,
+    "subtract with overflow"
+);
+uint_operator_impl!(
+    Mul,
+    mul,
+    MulAssign,
+    mul_assign,
+    checked_mul,
+    "multiply with overflow"
+);
+uint_operator_impl!(
+    Div,
+    div,
+    DivAssign,
+    div_assign,
+    checked_div,
+    "divide by zero"
+);
+uint_operator_impl!(
+    Rem,
+    rem,
+    RemAssign,
+    rem_assign,
+    checked_rem,
+    "calculate the remainder with a divisor of zero"
+);
+
+macro_rules! uint_bits_impl {
+    ($type:ident, $size:expr) => {
+        impl $type {
+            /// Returns the number of leading zero bits.
+            pub fn leading_zeros(&self) -> u32 {
+                match self.bytes.iter().rposition(|byte| *byte != 0) {
+                    Some(i) => ($size - 1 - i as u32) * 8 + self.bytes[i].leading_zeros(),
+                    None => $size * 8,
+                }
+            }
+
+            /// Returns the number of bits needed to represent the value.
+            pub fn bits(&self) -> u32 {
+                $size * 8 - self.leading_zeros()
+            }
+        }
+
+        impl PartialOrd for $type {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        /// Compares numerically, starting from the most significant byte.
+        impl Ord for $type {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.bytes.iter().rev().cmp(other.bytes.iter().rev())
+            }
+        }
+
+        /// Shifts towards the most significant bit. Shifting by the bit width or more yields zero.
+        impl core::ops::Shl<u32> for $type {
+            type Output = $type;
+
+            fn shl(self, rhs: u32) -> $type {
+                let mut ret = $type::default();
+                let (bytes, bits) = (rhs as usize / 8, rhs % 8);
+                for i in bytes..$size {
+                    ret.bytes[i] = self.bytes[i - bytes] << bits;
+                    if bits > 0 && i > bytes {
+                        ret.bytes[i] |= self.bytes[i - bytes - 1] >> (8 - bits);
+                    }
+                }
+                ret
+            }
+        }
+
+        /// Shifts towards the least significant bit. Shifting by the bit width or more yields zero.
+        impl core::ops::Shr<u32> for $type {
+            type Output = $type;
+
+            fn shr(self, rhs: u32) -> $type {
+                let mut ret = $type::default();
+                let (bytes, bits) = (rhs as usize / 8, rhs % 8);
+                for i in 0..$size - bytes.min($size) {
+                    ret.bytes[i] = self.bytes[i + bytes] >> bits;
+                    if bits > 0 && i + bytes + 1 < $size {
+                        ret.bytes[i] |= self.bytes[i + bytes + 1] << (8 - bits);
+                    }
+                }
+                ret
+            }
+        }
+
+        impl core::ops::ShlAssign<u32> for $type {
+            fn shl_assign(&mut self, rhs: u32) {
+                *self = *self << rhs;
+            }
+        }
+
+        impl core::ops::ShrAssign<u32> for $type {
+            fn shr_assign(&mut self, rhs: u32) {
+                *self = *self >> rhs;
+            }
+        }
+    };
+}
+
+uint_bits_impl!(Uint128, 16);
+uint_bits_impl!(Uint256, 32);
+
+/// Implements a bitwise operator byte by byte.
+macro_rules! bitwise_operator_impl {
+    ([$($generics:tt)*] $type:ty, $trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:tt) => {
+        impl<$($generics)*> core::ops::$trait for $type {
+            type Output = $type;
+
+            fn $method(mut self, rhs: $type) -> $type {
+                for (a, b) in self.bytes.iter_mut().zip(rhs.bytes.iter()) {
+                    *a $op *b;
+                }
+                self
+            }
+        }
+
+        impl<$($generics)*> core::ops::$assign_trait for $type {
+            fn $assign_method(&mut self, rhs: $type) {
+                *self = core::ops::$trait::$method(*self, rhs);
+            }
+        }
+    };
+}
+
+macro_rules! bitwise_impl {
+    ($type:ident) => {
+        bitwise_impl!([] $type);
+    };
+    ([$($generics:tt)*] $type:ty) => {
+        bitwise_operator_impl!([$($generics)*] $type, BitAnd, bitand, BitAndAssign, bitand_assign, &=);
+        bitwise_operator_impl!([$($generics)*] $type, BitOr, bitor, BitOrAssign, bitor_assign, |=);
+        bitwise_operator_impl!([$($generics)*] $type, BitXor, bitxor, BitXorAssign, bitxor_assign, ^=);
+
+        impl<$($generics)*> core::ops::Not for $type {
+            type Output = $type;
+
+            fn not(mut self) -> $type {
+                for byte in self.bytes.iter_mut() {
+                    *byte = !*byte;
+                }
+                self
+            }
+        }
+    };
+}
+
+bitwise_impl!(Uint128);
+bitwise_impl!(Uint256);
+bitwise_impl!(Int256);
+bitwise_impl!([const N: usize] FixedBytes<N>);
+
+/// The error returned when a checked conversion between integer types fails because the value is
+/// out of range.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TryFromIntError;
+
+impl core::fmt::Display for TryFromIntError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("out of range integral type conversion attempted")
+    }
+}
+
+impl Int256 {
+    /// The smallest value, -2^255.
+    pub const MIN: Int256 = Int256 {
+        bytes: {
+            let mut bytes = [0; 32];
+            bytes[31] = 0x80;
+            bytes
+        },
+    };
+    /// The largest value, 2^255 - 1.
+    pub const MAX: Int256 = Int256 {
+        bytes: {
+            let mut bytes = [0xff; 32];
+            bytes[31] = 0x7f;
+            bytes
+        },
+    };
+    /// The value -1.
+    pub const MINUS_ONE: Int256 = Int256 { bytes: [0xff; 32] };
+
+    /// Reinterprets the bits of an unsigned integer as a signed one.
+    pub fn from_bits(value: Uint256) -> Self {
+        Int256 { bytes: value.bytes }
+    }
+
+    /// Reinterprets the bits of the integer as an unsigned one.
+    pub fn to_bits(self) -> Uint256 {
+        Uint256 { bytes: self.bytes }
+    }
+
+    /// Returns true if the value is negative.
+    pub fn is_negative(&self) -> bool {
+        self.bytes[31] & 0x80 != 0
+    }
+
+    /// Returns true if the value is zero.
+    pub fn is_zero(&self) -> bool {
+        self.to_bits().is_zero()
+    }
+
+    /// Returns the absolute value as an unsigned integer, which cannot overflow.
+    pub fn unsigned_abs(self) -> Uint256 {
+        if self.is_negative() {
+            (-self).to_bits()
+        } else {
+            self.to_bits()
+        }
+    }
+
+    /// Calculates `-self`, returning whether an overflow occurred, which only happens for `MIN`.
+    pub fn overflowing_neg(self) -> (Self, bool) {
+        let ret = Int256::from_bits(Uint256::MIN.wrapping_sub(self.to_bits()));
+        (ret, self == Self::MIN)
+    }
+
+    /// Calculates `self + rhs`, returning whether an overflow occurred.
+    pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let ret = Int256::from_bits(self.to_bits().wrapping_add(rhs.to_bits()));
+        // Overflow occurs when both operands have the same sign, which differs from the result.
+        let overflow =
+            self.is_negative() == rhs.is_negative() && ret.is_negative() != self.is_negative();
+        (ret, overflow)
+    }
+
+    /// Calculates `self - rhs`, returning whether an overflow occurred.
+    pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let ret = Int256::from_bits(self.to_bits().wrapping_sub(rhs.to_bits()));
+        let overflow =
+            self.is_negative() != rhs.is_negative() && ret.is_negative() != self.is_negative();
+        (ret, overflow)
+    }
+
+    /// Calculates `self * rhs`, returning whether an overflow occurred.
+    pub fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+        let ret = Int256::from_bits(self.to_bits().wrapping_mul(rhs.to_bits()));
+        let (product, overflow) = self.unsigned_abs().overflowing_mul(rhs.unsigned_abs());
+        let negative = self.is_negative() != rhs.is_negative();
+        // The magnitude fits if it is below 2^255, or exactly 2^255 for a negative result.
+        let limit = Int256::MIN.to_bits();
+        let overflow = overflow || product > limit || (product == limit && !negative);
+        (ret, overflow)
+    }
+
+    /// Calculates `self / rhs` rounding towards zero, returning whether an overflow occurred,
+    /// which only happens for `MIN / -1`. Returns `None` if `rhs` is zero.
+    pub fn overflowing_div(self, rhs: Self) -> Option<(Self, bool)> {
+        let quotient = self.unsigned_abs().checked_div(rhs.unsigned_abs())?;
+        let quotient = Int256::from_bits(quotient);
+        if self.is_negative() != rhs.is_negative() {
+            Some((-quotient, false))
+        } else {
+            // Only `MIN / -1` yields a non-negative quotient of 2^255, which wraps to `MIN`.
+            Some((quotient, quotient.is_negative()))
+        }
+    }
+
+    /// Calculates `self % rhs`, with the sign of `self`. Returns `None` if `rhs` is zero.
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        let remainder = self.unsigned_abs().checked_rem(rhs.unsigned_abs())?;
+        let remainder = Int256::from_bits(remainder);
+        Some(if self.is_negative() {
+            -remainder
+        } else {
+            remainder
+        })
+    }
+
+    /// Calculates `-self`, returning `None` on overflow.
+    pub fn checked_neg(self) -> Option<Self> {
+        match self.overflowing_neg() {
+            (ret, false) => Some(ret),
+            _ => None,
+        }
+    }
+
+    /// Calculates `self + rhs`, returning `None` on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_add(rhs) {
+            (ret, false) => Some(ret),
+            _ => None,
+        }
+    }
+
+    /// Calculates `self - rhs`, returning `None` on overflow.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_sub(rhs) {
+            (ret, false) => Some(ret),
+            _ => None,
+        }
+    }
+
+    /// Calculates `self * rhs`, returning `None` on overflow.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_mul(rhs) {
+            (ret, false) => Some(ret),
+            _ => None,
+        }
+    }
+
+    /// Calculates `self / rhs`, returning `None` if `rhs` is zero or on overflow.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_div(rhs)? {
+            (ret, false) => Some(ret),
+            _ => None,
+        }
+    }
+
+    /// Calculates `self / rhs` like the EVM's `SDIV`: division by zero yields zero, and
+    /// `MIN / -1` yields `MIN`.
+    pub fn sdiv(self, rhs: Self) -> Self {
+        self.overflowing_div(rhs)
+            .map(|(ret, _)| ret)
+            .unwrap_or_default()
+    }
+
+    /// Calculates `self % rhs` like the EVM's `SMOD`: the result has the sign of `self`, and
+    /// division by zero yields zero.
+    pub fn smod(self, rhs: Self) -> Self {
+        self.checked_rem(rhs).unwrap_or_default()
+    }
+
+    /// Sign-extends the value from its byte at `byte`, counting from the least significant
+    /// byte, like the EVM's `SIGNEXTEND`. Values of `byte` of 31 or more leave it unchanged.
+    pub fn sign_extend(self, byte: u32) -> Self {
+        let byte = byte as usize;
+        if byte >= 31 {
+            return self;
+        }
+        let fill = if self.bytes[byte] & 0x80 != 0 {
+            0xff
+        } else {
+            0
+        };
+        let mut ret = self;
+        for b in ret.bytes[byte + 1..].iter_mut() {
+            *b = fill;
+        }
+        ret
+    }
+}
+
+impl core::convert::TryFrom<Uint256> for Int256 {
+    type Error = TryFromIntError;
+
+    /// Converts values below 2^255.
+    fn try_from(value: Uint256) -> Result<Self, TryFromIntError> {
+        match Int256::from_bits(value) {
+            ret if ret.is_negative() => Err(TryFromIntError),
+            ret => Ok(ret),
+        }
+    }
+}
+
+impl core::convert::TryFrom<Int256> for Uint256 {
+    type Error = TryFromIntError;
+
+    /// Converts non-negative values.
+    fn try_from(value: Int256) -> Result<Self, TryFromIntError> {
+        if value.is_negative() {
+            Err(TryFromIntError)
+        } else {
+            Ok(value.to_bits())
+        }
+    }
+}
+
+impl PartialOrd for Int256 {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares numerically, like the EVM's `SLT` and `SGT`.
+impl Ord for Int256 {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        other
+            .is_negative()
+            .cmp(&self.is_negative())
+            .then_with(|| self.to_bits().cmp(&other.to_bits()))
+    }
+}
+
+/// Negates, wrapping around for `MIN` like the EVM's `0 - x`.
+impl core::ops::Neg for Int256 {
+    type Output = Int256;
+
+    fn neg(self) -> Int256 {
+        self.overflowing_neg().0
+    }
+}
+
+/// Implements an arithmetic operator for `Int256` following the EVM's semantics.
+macro_rules! int_operator_impl {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $evm:expr) => {
+        impl core::ops::$trait for Int256 {
+            type Output = Int256;
+
+            fn $method(self, rhs: Int256) -> Int256 {
+                let evm: fn(Int256, Int256) -> Int256 = $evm;
+                evm(self, rhs)
+            }
+        }
+
+        impl core::ops::$assign_trait for Int256 {
+            fn $assign_method(&mut self, rhs: Int256) {
+                *self = core::ops::$trait::$method(*self, rhs);
+            }
+        }
+    };
+}
+
+int_operator_impl!(Add, add, AddAssign, add_assign, |a, b| a
+    .overflowing_add(b)
+    .0);
+int_operator_impl!(Sub, sub, SubAssign, sub_assign, |a, b| a
+    .overflowing_sub(b)
+    .0);
+int_operator_impl!(Mul, mul, MulAssign, mul_assign, |a, b| a
+    .overflowing_mul(b)
+    .0);
+int_operator_impl!(Div, div, DivAssign, div_assign, Int256::sdiv);
+int_operator_impl!(Rem, rem, RemAssign, rem_assign, Int256::smod);
+
+/// Shifts towards the most significant bit, like the EVM's `SHL`.
+impl core::ops::Shl<u32> for Int256 {
+    type Output = Int256;
+
+    fn shl(self, rhs: u32) -> Int256 {
+        Int256::from_bits(self.to_bits() << rhs)
+    }
+}
+
+/// Shifts arithmetically towards the least significant bit, like the EVM's `SAR`. Shifting by
+/// 256 or more yields zero, or -1 for negative values.
+impl core::ops::Shr<u32> for Int256 {
+    type Output = Int256;
+
+    fn shr(self, rhs: u32) -> Int256 {
+        let shifted = Int256::from_bits(self.to_bits() >> rhs);
+        if !self.is_negative() {
+            shifted
+        } else if rhs >= 256 {
+            Int256::MINUS_ONE
+        } else {
+            shifted | !Int256::from_bits(Uint256::MAX >> rhs)
+        }
+    }
+}
+
+impl core::ops::ShlAssign<u32> for Int256 {
+    fn shl_assign(&mut self, rhs: u32) {
+        *self = *self << rhs;
+    }
+}
+
+impl core::ops::ShrAssign<u32> for Int256 {
+    fn shr_assign(&mut self, rhs: u32) {
+        *self = *self >> rhs;
+    }
+}
+
+macro_rules! endian_bytes_impl {
+    ($type:ident, $size:expr) => {
+        impl $type {
+            /// Creates a value from its little-endian bytes, which is the layout of `bytes`.
+            pub fn from_le_bytes(bytes: [u8; $size]) -> Self {
+                $type { bytes }
+            }
+
+            /// Returns the little-endian bytes of the value, which is the layout of `bytes`.
+            pub fn to_le_bytes(self) -> [u8; $size] {
+                self.bytes
+            }
+
+            /// Creates a value from its big-endian bytes, as found in ABI-encoded data.
+            pub fn from_be_bytes(mut bytes: [u8; $size]) -> Self {
+                bytes.reverse();
+                $type { bytes }
+            }
+
+            /// Returns the big-endian bytes of the value, as found in ABI-encoded data.
+            pub fn to_be_bytes(self) -> [u8; $size] {
+                let mut bytes = self.bytes;
+                bytes.reverse();
+                bytes
+            }
+        }
+    };
+}
+
+endian_bytes_impl!(Uint128, 16);
+endian_bytes_impl!(Uint256, 32);
+endian_bytes_impl!(Int256, 32);
+
+/// Implements lossless conversions from native unsigned integers.
+macro_rules! from_native_impl {
+    ($type:ident, $($native:ident),+) => {
+        $(
+            impl From<$native> for $type {
+                fn from(value: $native) -> Self {
+                    let mut ret = $type::default();
+                    let bytes = value.to_le_bytes();
+                    ret.bytes[..bytes.len()].copy_from_slice(&bytes);
+                    ret
+                }
+            }
+        )+
+    };
+}
+
+/// Implements conversions to native unsigned integers, failing if the value does not fit.
+macro_rules! try_from_uint_impl {
+    ($type:ident, $($native:ident),+) => {
+        $(
+            impl core::convert::TryFrom<$type> for $native {
+                type Error = TryFromIntError;
+
+                fn try_from(value: $type) -> Result<Self, TryFromIntError> {
+                    let mut bytes = [0u8; core::mem::size_of::<$native>()];
+                    let (low, high) = value.bytes.split_at(bytes.len());
+                    if high.iter().any(|byte| *byte != 0) {
+                        return Err(TryFromIntError);
+                    }
+                    bytes.copy_from_slice(low);
+                    Ok($native::from_le_bytes(bytes))
+                }
+            }
+        )+
+    };
+}
+
+from_native_impl!(Uint128, u8, u16, u32, u64, u128);
+from_native_impl!(Uint256, u8, u16, u32, u64, u128);
+
+try_from_uint_impl!(Uint128, u8, u16, u32, u64);
+try_from_uint_impl!(Uint256, u8, u16, u32, u64, u128, Uint128);
+
+impl From<Uint128> for Uint256 {
+    fn from(value: Uint128) -> Self {
+        Uint256::from(u128::from(value))
+    }
+}
+
+/// The error returned when parsing a type from a string fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The string has no digits.
+    Empty,
+    /// The string contains a character which is not a digit.
+    InvalidDigit,
+    /// The string has the wrong number of hexadecimal digits for a fixed-size byte array.
+    InvalidLength,
+    /// The number is out of the range of the type.
+    Overflow,
+    /// The address is in mixed case, but does not match its EIP-55 checksum.
+    InvalidChecksum,
+    /// The amount has more fractional digits than its unit allows, such as `1.5` wei.
+    TooPrecise,
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            ParseError::Empty => "cannot parse from empty string",
+            ParseError::InvalidDigit => "invalid digit found in string",
+            ParseError::InvalidLength => "invalid number of digits",
+            ParseError::Overflow => "number too large to fit in target type",
+            ParseError::InvalidChecksum => "invalid address checksum",
+            ParseError::TooPrecise => "too many decimal places for the unit",
+        })
+    }
+}
+
+fn hex_digit(c: u8) -> Result<u8, ParseError> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(ParseError::InvalidDigit),
+    }
+}
+
+/// Parses hexadecimal digits as a number into little-endian `bytes`.
+fn parse_hex_number(digits: &[u8], bytes: &mut [u8]) -> Result<(), ParseError> {
+    if digits.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    for (i, digit) in digits.iter().rev().enumerate() {
+        let digit = hex_digit(*digit)?;
+        if digit == 0 {
+            continue;
+        }
+        match bytes.get_mut(i / 2) {
+            Some(byte) => *byte |= digit << (4 * (i % 2)),
+            None => return Err(ParseError::Overflow),
+        }
+    }
+    Ok(())
+}
+
+/// Parses a number into little-endian `bytes`, in hexadecimal if prefixed with `0x`, and in
+/// decimal otherwise.
+fn parse_number(s: &str, bytes: &mut [u8]) -> Result<(), ParseError> {
+    if let Some(digits) = s.strip_prefix("0x") {
+        return parse_hex_number(digits.as_bytes(), bytes);
+    }
+    if s.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    for c in s.bytes() {
+        if !c.is_ascii_digit() {
+            return Err(ParseError::InvalidDigit);
+        }
+        let mut carry = u16::from(c - b'0');
+        for byte in bytes.iter_mut() {
+            let product = u16::from(*byte) * 10 + carry;
+            *byte = product as u8;
+            carry = product >> 8;
+        }
+        if carry != 0 {
+            return Err(ParseError::Overflow);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the hexadecimal digits of a little-endian number, without leading zeros.
+fn fmt_hex_number(
+    bytes: &[u8],
+    upper: bool,
+    non_negative: bool,
+    f: &mut core::fmt::Formatter,
+) -> core::fmt::Result {
+    let table: &[u8; 16] = if upper {
+        b"0123456789ABCDEF"
+    } else {
+        b"0123456789abcdef"
+    };
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    for byte in bytes.iter() {
+        buf[63 - len] = table[usize::from(byte & 0xf)];
+        buf[62 - len] = table[usize::from(byte >> 4)];
+        len += 2;
+    }
+    let digits = &buf[64 - len..];
+    let start = digits
+        .iter()
+        .position(|digit| *digit != b'0')
+        .unwrap_or(len - 1);
+    let digits = core::str::from_utf8(&digits[start..]).expect("ASCII digits");
+    f.pad_integral(non_negative, "0x", digits)
+}
+
+/// Writes the decimal digits of a little-endian number.
+fn fmt_decimal_number(
+    bytes: &[u8],
+    non_negative: bool,
+    f: &mut core::fmt::Formatter,
+) -> core::fmt::Result {
+    // 2^256 has 78 decimal digits.
+    let mut buf = [0u8; 78];
+    let mut number = [0u8; 32];
+    number[..bytes.len()].copy_from_slice(bytes);
+    let mut start = buf.len();
+    loop {
+        let mut remainder = 0u16;
+        for byte in number.iter_mut().rev() {
+            let dividend = (remainder << 8) | u16::from(*byte);
+            *byte = (dividend / 10) as u8;
+            remainder = dividend % 10;
+        }
+        start -= 1;
+        buf[start] = b'0' + remainder as u8;
+        if number.iter().all(|byte| *byte == 0) {
+            break;
+        }
+    }
+    let digits = core::str::from_utf8(&buf[start..]).expect("ASCII digits");
+    f.pad_integral(non_negative, "", digits)
+}
+
+macro_rules! uint_fmt_impl {
+    ($type:ident) => {
+        /// Formats the number in decimal.
+        impl core::fmt::Display for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                fmt_decimal_number(&self.bytes, true, f)
+            }
+        }
+
+        impl core::fmt::Debug for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                core::fmt::Display::fmt(self, f)
+            }
+        }
+
+        impl core::fmt::LowerHex for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                fmt_hex_number(&self.bytes, false, true, f)
+            }
+        }
+
+        impl core::fmt::UpperHex for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                fmt_hex_number(&self.bytes, true, true, f)
+            }
+        }
+
+        /// Parses a number in hexadecimal if prefixed with `0x`, and in decimal otherwise.
+        impl core::str::FromStr for $type {
+            type Err = ParseError;
+
+            fn from_str(s: &str) -> Result<Self, ParseError> {
+                let mut ret = $type::default();
+                parse_number(s, &mut ret.bytes)?;
+                Ok(ret)
+            }
+        }
+    };
+}
+
+uint_fmt_impl!(Uint128);
+uint_fmt_impl!(Uint256);
+
+/// Formats the number in decimal.
+impl core::fmt::Display for Int256 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fmt_decimal_number(&self.unsigned_abs().bytes, !self.is_negative(), f)
+    }
+}
+
+impl core::fmt::Debug for Int256 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Display::fmt(self, f)
+    }
+}
+
+/// Formats the two's complement representation, like the primitive signed integers.
+impl core::fmt::LowerHex for Int256 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fmt_hex_number(&self.bytes, false, true, f)
+    }
+}
+
+/// Formats the two's complement representation, like the primitive signed integers.
+impl core::fmt::UpperHex for Int256 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fmt_hex_number(&self.bytes, true, true, f)
+    }
+}
+
+/// Parses a number with an optional sign, in hexadecimal if prefixed with `0x`, and in decimal
+/// otherwise.
+impl core::str::FromStr for Int256 {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let magnitude = Int256::from_bits(digits.parse::<Uint256>()?);
+        match (negative, magnitude.is_negative()) {
+            (false, false) => Ok(magnitude),
+            (true, false) => Ok(-magnitude),
+            (true, true) if magnitude == Int256::MIN => Ok(magnitude),
+            _ => Err(ParseError::Overflow),
+        }
+    }
+}
+
+macro_rules! bytes_fmt_impl {
+    ($type:ident) => {
+        bytes_fmt_impl!([] $type);
+    };
+    ([$($generics:tt)*] $type:ty) => {
+        /// Formats the bytes in lowercase hexadecimal, prefixed with `0x`.
+        impl<$($generics)*> core::fmt::Display for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(f, "{:#x}", self)
+            }
+        }
+
+        impl<$($generics)*> core::fmt::Debug for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                core::fmt::Display::fmt(self, f)
+            }
+        }
+
+        /// Formats the bytes in lowercase hexadecimal, prefixed with `0x` if the alternate flag
+        /// is given.
+        impl<$($generics)*> core::fmt::LowerHex for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                if f.alternate() {
+                    f.write_str("0x")?;
+                }
+                for byte in self.bytes.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+        }
+
+        /// Formats the bytes in uppercase hexadecimal, prefixed with `0x` if the alternate flag
+        /// is given.
+        impl<$($generics)*> core::fmt::UpperHex for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                if f.alternate() {
+                    f.write_str("0x")?;
+                }
+                for byte in self.bytes.iter() {
+                    write!(f, "{:02X}", byte)?;
+                }
+                Ok(())
+            }
+        }
+
+        /// Parses exactly as many hexadecimal digits as there are bytes, optionally prefixed
+        /// with `0x`.
+        impl<$($generics)*> core::str::FromStr for $type {
+            type Err = ParseError;
+
+            fn from_str(s: &str) -> Result<Self, ParseError> {
+                let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+                let mut ret = <$type>::default();
+                if digits.len() != 2 * ret.bytes.len() {
+                    return Err(ParseError::InvalidLength);
+                }
+                for (byte, pair) in ret.bytes.iter_mut().zip(digits.chunks(2)) {
+                    *byte = (hex_digit(pair[0])? << 4) | hex_digit(pair[1])?;
+                }
+                Ok(ret)
+            }
+        }
+    };
+}
+
+bytes_fmt_impl!([const N: usize] FixedBytes<N>);
+
+macro_rules! checksum_impl {
+    ($type:ident) => {
+        impl $type {
+            /// Returns the hexadecimal digits of the address in its EIP-55 checksum encoding.
+            fn checksum_digits(&self) -> [u8; 40] {
+                const DIGITS: &[u8; 16] = b"0123456789abcdef";
+                let mut digits = [0u8; 40];
+                for (i, byte) in self.bytes.iter().enumerate() {
+                    digits[2 * i] = DIGITS[usize::from(byte >> 4)];
+                    digits[2 * i + 1] = DIGITS[usize::from(byte & 0xf)];
+                }
+                let hash = crate::crypto::keccak256(&digits).bytes;
+                for (i, digit) in digits.iter_mut().enumerate() {
+                    let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+                    if nibble >= 8 {
+                        *digit = digit.to_ascii_uppercase();
+                    }
+                }
+                digits
+            }
+
+            /// Writes an address into `buf` in its EIP-55 mixed-case checksum encoding, prefixed
+            /// with `0x`, and returns it as a string. Unlike `to_checksum_string`, this does not
+            /// need `std`.
+            pub fn to_checksum_str<'a>(&self, buf: &'a mut [u8; 42]) -> &'a str {
+                buf[..2].copy_from_slice(b"0x");
+                buf[2..].copy_from_slice(&self.checksum_digits());
+                core::str::from_utf8(buf).expect("ASCII digits")
+            }
+
+            /// Formats an address in its EIP-55 mixed-case checksum encoding, prefixed with `0x`.
+            #[cfg(feature = "std")]
+            pub fn to_checksum_string(&self) -> String {
+                self.to_checksum_str(&mut [0; 42]).to_string()
+            }
+
+            /// Parses an address, optionally prefixed with `0x`, validating its EIP-55 checksum the way
+            /// wallets do: all-lowercase and all-uppercase addresses carry no checksum and are accepted,
+            /// while mixed-case addresses must match their checksum exactly.
+            pub fn from_checksum_str(s: &str) -> Result<Self, ParseError> {
+                let address: Self = s.parse()?;
+                let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+                let lowercase = digits.iter().all(|c| !c.is_ascii_uppercase());
+                let uppercase = digits.iter().all(|c| !c.is_ascii_lowercase());
+                if lowercase || uppercase || digits == &address.checksum_digits()[..] {
+                    Ok(address)
+                } else {
+                    Err(ParseError::InvalidChecksum)
+                }
+            }
+        }
+    };
+}
+
+checksum_impl!(Bytes20);
+
+/// Implements lossless conversions from and to an unsigned integer of `primitive-types`, which is
+/// stored in little-endian 64-bit limbs and serialized big-endian.
+#[cfg(feature = "primitive-types")]
+macro_rules! primitive_uint_impl {
+    ($type:ident, $other:ident) => {
+        impl From<primitive_types::$other> for $type {
+            fn from(value: primitive_types::$other) -> Self {
+                $type {
+                    bytes: value.to_little_endian(),
+                }
+            }
+        }
+
+        impl From<$type> for primitive_types::$other {
+            fn from(value: $type) -> Self {
+                primitive_types::$other::from_little_endian(&value.bytes)
+            }
+        }
+    };
+}
+
+/// Implements conversions from and to a fixed-size hash of `primitive-types`, which has the same
+/// byte order.
+#[cfg(feature = "primitive-types")]
+macro_rules! primitive_hash_impl {
+    ($type:ident, $other:ident) => {
+        impl From<primitive_types::$other> for $type {
+            fn from(value: primitive_types::$other) -> Self {
+                $type { bytes: value.0 }
+            }
+        }
+
+        impl From<$type> for primitive_types::$other {
+            fn from(value: $type) -> Self {
+                primitive_types::$other(value.bytes)
+            }
+        }
+    };
+}
+
+cfg_if! {
+    if #[cfg(feature = "primitive-types")] {
+        primitive_uint_impl!(Uint128, U128);
+        primitive_uint_impl!(Uint256, U256);
+        primitive_hash_impl!(Bytes20, H160);
+        primitive_hash_impl!(Bytes32, H256);
+    }
+}
+
+/// Deserializes a type from a string with its `FromStr` implementation.
+#[cfg(feature = "serde")]
+struct ParseVisitor<T>(core::marker::PhantomData<T>, &'static str);
+
+#[cfg(feature = "serde")]
+impl<'de, T: core::str::FromStr<Err = ParseError>> serde::de::Visitor<'de> for ParseVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.1)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<T, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+/// Implements serialization as a string with the given format, and deserialization with
+/// `FromStr`. Neither allocates.
+#[cfg(feature = "serde")]
+macro_rules! serde_impl {
+    ($type:ident, $format:expr, $expecting:expr) => {
+        serde_impl!([] $type, $format, $expecting);
+    };
+    ([$($generics:tt)*] $type:ty, $format:expr, $expecting:expr) => {
+        impl<$($generics)*> serde::Serialize for $type {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(&format_args!($format, self))
+            }
+        }
+
+        impl<'de, $($generics)*> serde::Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_str(ParseVisitor(core::marker::PhantomData, $expecting))
+            }
+        }
+    };
+}
+
+// Unsigned integers are quantities in the JSON-RPC convention: hexadecimal without leading
+// zeros. Decimal strings are accepted as well.
+cfg_if! {
+    if #[cfg(feature = "serde")] {
+        serde_impl!(Uint128, "{:#x}", "a hexadecimal or decimal quantity string");
+        serde_impl!(Uint256, "{:#x}", "a hexadecimal or decimal quantity string");
+        serde_impl!(Int256, "{}", "a signed decimal or hexadecimal string");
+        serde_impl!([const N: usize] FixedBytes<N>, "{}", "a hexadecimal data string");
+    }
+}
+
+/// Implements explicit conversions in both directions between a newtype and each of the given
+/// types of the same size.
+#[cfg(feature = "newtypes")]
+macro_rules! newtype_conversion_impl {
+    ($type:ident => $($other:ident),+) => {
+        $(
+            impl From<$other> for $type {
+                fn from(value: $other) -> Self {
+                    $type { bytes: value.bytes }
+                }
+            }
+
+            impl From<$type> for $other {
+                fn from(value: $type) -> Self {
+                    $other { bytes: value.bytes }
+                }
+            }
+        )+
+    };
+}
+
+cfg_if! {
+    if #[cfg(feature = "newtypes")] {
+        from_primitive_impl!(u8, 16, EtherValue);
+        from_primitive_impl!(u8, 20, Address);
+        from_primitive_impl!(u8, 32, StorageKey);
+        from_primitive_impl!(u8, 32, StorageValue);
+        from_primitive_impl!(u8, 32, LogTopic);
+        from_primitive_impl!(u8, 32, Hash);
+
+        from_primitive_ref_impl!(u8, 16, EtherValue);
+        from_primitive_ref_impl!(u8, 20, Address);
+        from_primitive_ref_impl!(u8, 32, StorageKey);
+        from_primitive_ref_impl!(u8, 32, StorageValue);
+        from_primitive_ref_impl!(u8, 32, LogTopic);
+        from_primitive_ref_impl!(u8, 32, Hash);
+
+        from_type_for_primitive_impl!(EtherValue, 16, u8);
+        from_type_for_primitive_impl!(Address, 20, u8);
+        from_type_for_primitive_impl!(StorageKey, 32, u8);
+        from_type_for_primitive_impl!(StorageValue, 32, u8);
+        from_type_for_primitive_impl!(LogTopic, 32, u8);
+        from_type_for_primitive_impl!(Hash, 32, u8);
+
+        newtype_conversion_impl!(EtherValue => Uint128);
+        newtype_conversion_impl!(Address => Bytes20);
+        newtype_conversion_impl!(StorageKey => Bytes32, StorageValue, LogTopic, Hash);
+        newtype_conversion_impl!(StorageValue => Bytes32, LogTopic, Hash);
+        newtype_conversion_impl!(LogTopic => Bytes32, Hash);
+        newtype_conversion_impl!(Hash => Bytes32);
+
+        u128_arithmetic_impl!(EtherValue);
+        uint_arithmetic_impl!(EtherValue, 16);
+        uint_bits_impl!(EtherValue, 16);
+        endian_bytes_impl!(EtherValue, 16);
+        from_native_impl!(EtherValue, u8, u16, u32, u64, u128);
+        try_from_uint_impl!(EtherValue, u8, u16, u32, u64);
+        try_from_uint_impl!(Uint256, EtherValue);
+
+        impl From<EtherValue> for Uint256 {
+            fn from(value: EtherValue) -> Self {
+                Uint256::from(u128::from(value))
+            }
+        }
+        uint_fmt_impl!(EtherValue);
+
+        bytes_impl!(Address, 20);
+        bytes_impl!(StorageKey, 32);
+        bytes_impl!(StorageValue, 32);
+        bytes_impl!(LogTopic, 32);
+        bytes_impl!(Hash, 32);
+
+        bitwise_impl!(EtherValue);
+        bitwise_impl!(Address);
+        bitwise_impl!(StorageKey);
+        bitwise_impl!(StorageValue);
+        bitwise_impl!(LogTopic);
+        bitwise_impl!(Hash);
+
+        bytes_fmt_impl!(Address);
+        bytes_fmt_impl!(StorageKey);
+        bytes_fmt_impl!(StorageValue);
+        bytes_fmt_impl!(LogTopic);
+        bytes_fmt_impl!(Hash);
+
+        checksum_impl!(Address);
+
+        #[cfg(feature = "serde")]
+        serde_impl!(EtherValue, "{:#x}", "a hexadecimal or decimal quantity string");
+        #[cfg(feature = "serde")]
+        serde_impl!(Address, "{}", "a hexadecimal data string");
+        #[cfg(feature = "serde")]
+        serde_impl!(StorageKey, "{}", "a hexadecimal data string");
+        #[cfg(feature = "serde")]
+        serde_impl!(StorageValue, "{}", "a hexadecimal data string");
+        #[cfg(feature = "serde")]
+        serde_impl!(LogTopic, "{}", "a hexadecimal data string");
+        #[cfg(feature = "serde")]
+        serde_impl!(Hash, "{}", "a hexadecimal data string");
+
+        #[cfg(feature = "primitive-types")]
+        primitive_uint_impl!(EtherValue, U128);
+        #[cfg(feature = "primitive-types")]
+        primitive_hash_impl!(Address, H160);
+        #[cfg(feature = "primitive-types")]
+        primitive_hash_impl!(StorageKey, H256);
+        #[cfg(feature = "primitive-types")]
+        primitive_hash_impl!(StorageValue, H256);
+        #[cfg(feature = "primitive-types")]
+        primitive_hash_impl!(LogTopic, H256);
+        #[cfg(feature = "primitive-types")]
+        primitive_hash_impl!(Hash, H256);
+    }
+}
+
+/// Returns the value of a hexadecimal digit at compile time.
+const fn const_hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        b'A'..=b'F' => c - b'A' + 10,
+        _ => panic!("invalid hexadecimal digit in literal"),
+    }
+}
+
+/// Strips the `0x` prefix of a literal at compile time.
+const fn strip_hex_prefix(s: &[u8]) -> (&[u8], bool) {
+    match s {
+        [b'0', b'x', digits @ ..] => (digits, true),
+        _ => (s, false),
+    }
+}
+
+/// Parses exactly `2 * N` hexadecimal digits, optionally prefixed with `0x`, at compile time.
+/// Used by the `bytes32!` macro.
+#[doc(hidden)]
+pub const fn parse_hex_literal<const N: usize>(s: &str) -> [u8; N] {
+    let (digits, _) = strip_hex_prefix(s.as_bytes());
+    if digits.len() != 2 * N {
+        panic!("invalid number of hexadecimal digits in literal");
+    }
+    let mut ret = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        ret[i] = (const_hex_digit(digits[2 * i]) << 4) | const_hex_digit(digits[2 * i + 1]);
+        i += 1;
+    }
+    ret
+}
+
+/// Parses an address of 40 hexadecimal digits, optionally prefixed with `0x`, at compile time.
+/// Mixed-case digits must match the EIP-55 checksum of the address, as in `from_checksum_str`.
+/// Used by the `address!` macro.
+#[doc(hidden)]
+pub const fn parse_address_literal(s: &str) -> [u8; 20] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let bytes = parse_hex_literal::<20>(s);
+    let (digits, _) = strip_hex_prefix(s.as_bytes());
+
+    let (mut lowercase, mut uppercase) = (false, false);
+    let mut i = 0;
+    while i < 40 {
+        lowercase |= digits[i].is_ascii_lowercase();
+        uppercase |= digits[i].is_ascii_uppercase();
+        i += 1;
+    }
+    if !(lowercase && uppercase) {
+        return bytes;
+    }
+
+    let mut lower = [0u8; 40];
+    let mut i = 0;
+    while i < 20 {
+        lower[2 * i] = DIGITS[(bytes[i] >> 4) as usize];
+        lower[2 * i + 1] = DIGITS[(bytes[i] & 0xf) as usize];
+        i += 1;
+    }
+    let hash = crate::crypto::const_keccak256(&lower);
+    let mut i = 0;
+    while i < 40 {
+        let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+        if lower[i].is_ascii_alphabetic() && digits[i].is_ascii_uppercase() != (nibble >= 8) {
+            panic!("invalid EIP-55 checksum in address literal");
+        }
+        i += 1;
+    }
+    bytes
+}
+
+/// Parses a 256-bit number into little-endian bytes at compile time, in hexadecimal if prefixed
+/// with `0x`, and in decimal otherwise. Used by the `uint256!` macro.
+#[doc(hidden)]
+pub const fn parse_number_literal(s: &str) -> [u8; 32] {
+    let (digits, hex) = strip_hex_prefix(s.as_bytes());
+    if digits.is_empty() {
+        panic!("empty number literal");
+    }
+    let radix: u16 = if hex { 16 } else { 10 };
+    let mut ret = [0u8; 32];
+    let mut i = 0;
+    while i < digits.len() {
+        let mut carry = if hex {
+            const_hex_digit(digits[i]) as u16
+        } else if digits[i].is_ascii_digit() {
+            (digits[i] - b'0') as u16
+        } else {
+            panic!("invalid decimal digit in literal")
+        };
+        let mut j = 0;
+        while j < 32 {
+            let product = ret[j] as u16 * radix + carry;
+            ret[j] = product as u8;
+            carry = product >> 8;
+            j += 1;
+        }
+        if carry != 0 {
+            panic!("number literal out of range");
+        }
+        i += 1;
+    }
+    ret
+}
+
+/// Parses a 128-bit number of wei into little-endian bytes at compile time, as
+/// `parse_number_literal` does. Used by the `ether_value!` macro.
+#[doc(hidden)]
+pub const fn parse_wei_literal(s: &str) -> [u8; 16] {
+    let number = parse_number_literal(s);
+    let mut ret = [0u8; 16];
+    let mut i = 0;
+    while i < 32 {
+        if i < 16 {
+            ret[i] = number[i];
+        } else if number[i] != 0 {
+            panic!("number literal out of range");
+        }
+        i += 1;
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Address, Bytes20, Bytes32, EtherValue, FixedBytes, Int256, LogTopic, ParseError,
+        StorageKey, TryFromIntError, Uint128, Uint256,
+    };
+    use core::convert::TryFrom;
+
+    macro_rules! test_conversions {
+        ($type: ident, $size: expr, $test_name: ident) => {
+            #[test]
+            fn $test_name() {
+                let raw = [1; $size];
+
+                let uint = $type::from(raw);
+                assert_eq!(uint.bytes[$size - 1], 1);
+                let uint = $type::from(&raw);
+                assert_eq!(uint.bytes[$size - 1], 1);
+
+                let uint: $type = raw.into();
+                assert_eq!(uint.bytes[$size - 1], 1);
+                let uint: $type = (&raw).into();
+                assert_eq!(uint.bytes[$size - 1], 1);
+
+                let r: [u8; $size] = uint.into();
+                assert_eq!(r[$size - 1], 1);
+            }
+        };
+    }
+
+    test_conversions!(Uint128, 16, test_uint128);
+    test_conversions!(Uint256, 32, test_uint256);
+    test_conversions!(Bytes20, 20, test_bytes20);
+    test_conversions!(Bytes32, 32, test_bytes32);
+    test_conversions!(Int256, 32, test_int256);
+
+    fn uint128(value: u128) -> Uint128 {
+        Uint128::from(value.to_le_bytes())
+    }
+
+    fn small(value: u8) -> Uint256 {
+        let mut ret = Uint256::default();
+        ret.bytes[0] = value;
+        ret
+    }
+
+    fn pow2(exponent: usize) -> Uint256 {
+        let mut ret = Uint256::default();
+        ret.bytes[exponent / 8] = 1 << (exponent % 8);
+        ret
+    }
+
+    #[test]
+    fn uint128_arithmetic() {
+        let values = [
+            0,
+            1,
+            2,
+            7,
+            u64::MAX as u128,
+            u128::MAX / 3,
+            u128::MAX - 1,
+            u128::MAX,
+        ];
+        for a in values.iter() {
+            for b in values.iter() {
+                let (x, y) = (uint128(*a), uint128(*b));
+                assert_eq!(x.checked_add(y), a.checked_add(*b).map(uint128));
+                assert_eq!(x.checked_sub(y), a.checked_sub(*b).map(uint128));
+                assert_eq!(x.checked_mul(y), a.checked_mul(*b).map(uint128));
+                assert_eq!(x.checked_div(y), a.checked_div(*b).map(uint128));
+                assert_eq!(x.checked_rem(y), a.checked_rem(*b).map(uint128));
+                assert_eq!(x.wrapping_mul(y), uint128(a.wrapping_mul(*b)));
+                assert_eq!(x.saturating_add(y), uint128(a.saturating_add(*b)));
+                assert_eq!(x.saturating_sub(y), uint128(a.saturating_sub(*b)));
+                assert_eq!(x.overflowing_sub(y).1, a.overflowing_sub(*b).1);
+            }
+        }
+    }
+
+    #[test]
+    fn uint256_arithmetic() {
+        let (max, one) = (Uint256::MAX, small(1));
+        assert_eq!(max.overflowing_add(one), (Uint256::MIN, true));
+        assert_eq!(Uint256::MIN.overflowing_sub(one), (max, true));
+        assert_eq!(pow2(255).overflowing_mul(small(2)), (Uint256::MIN, true));
+        assert_eq!(max.checked_mul(max), None);
+        assert_eq!(max.wrapping_mul(max), one);
+        assert_eq!(max.saturating_mul(small(2)), max);
+        assert_eq!(pow2(128) * pow2(127), pow2(255));
+        assert_eq!((pow2(200) + one) - pow2(200), one);
+        assert_eq!(pow2(64) + pow2(64), pow2(65));
+
+        assert_eq!(pow2(255) / pow2(100), pow2(155));
+        assert_eq!((pow2(255) + small(7)) % pow2(100), small(7));
+        assert_eq!(max / max, one);
+        assert_eq!(max % pow2(255), pow2(255) - one);
+        let (quotient, remainder) = (pow2(70) / small(3), pow2(70) % small(3));
+        assert_eq!(remainder, one);
+        assert_eq!(quotient * small(3) + remainder, pow2(70));
+        assert_eq!(one.checked_div(Uint256::MIN), None);
+        assert_eq!(one.checked_rem(Uint256::MIN), None);
+
+        let mut value = pow2(100);
+        value += one;
+        value *= small(2);
+        value -= small(2);
+        value /= pow2(101);
+        assert_eq!(value, one);
+        assert!(!value.is_zero());
+        value %= one;
+        assert!(value.is_zero());
+    }
+
+    #[test]
+    fn uint128_bits() {
+        let values = [0, 1, 0x80, 0x1234_5678, u64::MAX as u128 + 1, u128::MAX];
+        for a in values.iter() {
+            let x = uint128(*a);
+            assert_eq!(x.leading_zeros(), a.leading_zeros());
+            assert_eq!(x.bits(), 128 - a.leading_zeros());
+            assert_eq!(!x, uint128(!a));
+            for shift in [0, 1, 7, 8, 9, 63, 64, 127].iter() {
+                assert_eq!(x << *shift, uint128(a << shift));
+                assert_eq!(x >> *shift, uint128(a >> shift));
+            }
+            for b in values.iter() {
+                let y = uint128(*b);
+                assert_eq!(x.cmp(&y), a.cmp(b));
+                assert_eq!(x & y, uint128(a & b));
+                assert_eq!(x | y, uint128(a | b));
+                assert_eq!(x ^ y, uint128(a ^ b));
+            }
+        }
+        assert!(uint128(1) << 128 == Uint128::MIN);
+        assert!(Uint128::MAX >> 200 == Uint128::MIN);
+    }
+
+    #[test]
+    fn uint256_bits() {
+        assert_eq!(Uint256::MIN.leading_zeros(), 256);
+        assert_eq!(Uint256::MIN.bits(), 0);
+        assert_eq!(pow2(200).bits(), 201);
+        assert_eq!(pow2(255).leading_zeros(), 0);
+        assert_eq!(small(1) << 200, pow2(200));
+        assert_eq!(pow2(200) >> 137, pow2(63));
+        assert_eq!(Uint256::MAX >> 255, small(1));
+        assert_eq!(Uint256::MAX << 256, Uint256::MIN);
+        assert_eq!(!Uint256::MIN, Uint256::MAX);
+        assert_eq!(
+            (Uint256::MAX << 8) ^ (Uint256::MAX >> 8),
+            small(0xff)
+                | pow2(255)
+                | pow2(254)
+                | pow2(253)
+                | pow2(252)
+                | pow2(251)
+                | pow2(250)
+                | pow2(249)
+                | pow2(248)
+        );
+
+        // Numeric ordering differs from the byte-wise ordering of the little-endian layout.
+        assert!(pow2(8) > small(0xff));
+        assert!(pow2(255) > Uint256::MAX >> 1);
+        let mut values = vec![pow2(100), small(2), pow2(9), small(1)];
+        values.sort();
+        assert_eq!(values, vec![small(1), small(2), pow2(9), pow2(100)]);
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(pow2(9));
+        assert!(set.contains(&pow2(9)));
+        assert!(!set.contains(&pow2(8)));
+    }
+
+    #[test]
+    fn bytes_bitwise() {
+        let mut a = Bytes32::from([0b1100; 32]);
+        a &= Bytes32::from([0b1010; 32]);
+        assert_eq!(a, Bytes32::from([0b1000; 32]));
+        assert_eq!(
+            Bytes20::from([0b1100; 20]) ^ Bytes20::from([0b1010; 20]),
+            Bytes20::from([0b0110; 20])
+        );
+        assert_eq!(!Bytes20::default(), Bytes20::from([0xff; 20]));
+    }
+
+    /// Sign-extends an `i128` to an `Int256`.
+    fn int256(value: i128) -> Int256 {
+        let mut ret = Int256::from([if value < 0 { 0xff } else { 0 }; 32]);
+        ret.bytes[..16].copy_from_slice(&value.to_le_bytes());
+        ret
+    }
+
+    #[test]
+    fn int256_arithmetic() {
+        let values = [0, 1, -1, 7, -7, 1 << 100, -(1 << 100), i128::MAX, i128::MIN];
+        for a in values.iter() {
+            let x = int256(*a);
+            assert_eq!(x.is_negative(), *a < 0);
+            assert_eq!(-x, int256(0) - x);
+            for b in values.iter() {
+                let y = int256(*b);
+                assert_eq!(x.cmp(&y), a.cmp(b));
+                if let Some(sum) = a.checked_add(*b) {
+                    assert_eq!(x + y, int256(sum));
+                }
+                if let Some(product) = a.checked_mul(*b) {
+                    assert_eq!(x * y, int256(product));
+                }
+                if let Some(quotient) = a.checked_div(*b) {
+                    assert_eq!(x / y, int256(quotient));
+                }
+                if let Some(remainder) = a.checked_rem(*b) {
+                    assert_eq!(x % y, int256(remainder));
+                }
+                assert_eq!(x.checked_sub(y), Some(int256(*a) - int256(*b)));
+            }
+        }
+    }
+
+    #[test]
+    fn int256_evm_semantics() {
+        let (min, max, minus_one) = (Int256::MIN, Int256::MAX, Int256::MINUS_ONE);
+        assert_eq!(min / minus_one, min);
+        assert_eq!(min.checked_div(minus_one), None);
+        assert_eq!(min.overflowing_div(minus_one), Some((min, true)));
+        assert_eq!(min % minus_one, int256(0));
+        assert_eq!(int256(5) / int256(0), int256(0));
+        assert_eq!(int256(5) % int256(0), int256(0));
+        assert_eq!(int256(-7) / int256(2), int256(-3));
+        assert_eq!(int256(-7) % int256(2), int256(-1));
+        assert_eq!(int256(7) % int256(-2), int256(1));
+        assert_eq!(-min, min);
+        assert_eq!(min.checked_neg(), None);
+        assert_eq!(max + int256(1), min);
+        assert_eq!(max.checked_add(int256(1)), None);
+        assert_eq!(min.checked_sub(int256(1)), None);
+        assert_eq!(min.checked_mul(minus_one), None);
+        assert_eq!(minus_one.checked_mul(min), None);
+        assert_eq!(int256(-1 << 100).checked_mul(int256(1) << 200), None);
+        assert_eq!(int256(-1 << 100) * int256(1 << 100), -(int256(1) << 200));
+        assert_eq!((int256(1) << 254).checked_mul(int256(-2)), Some(min));
+        assert_eq!(min.unsigned_abs(), Uint256::MAX - min.to_bits() + small(1));
+        assert!(min < max && min < minus_one && minus_one < int256(0));
+
+        assert_eq!(int256(-16) >> 2, int256(-4));
+        assert_eq!(int256(-1) >> 255, minus_one);
+        assert_eq!(min >> 300, minus_one);
+        assert_eq!(max >> 300, int256(0));
+        assert_eq!(int256(16) >> 2, int256(4));
+        assert_eq!(int256(-1) << 255, min);
+
+        assert_eq!(int256(0xff).sign_extend(0), minus_one);
+        assert_eq!(int256(0x7f).sign_extend(0), int256(0x7f));
+        assert_eq!(int256(0x1_8000).sign_extend(1), int256(-0x8000));
+        assert_eq!(min.sign_extend(31), min);
+    }
+
+    #[test]
+    fn int256_conversions() {
+        assert_eq!(
+            Int256::try_from(pow2(254)),
+            Ok(Int256::from_bits(pow2(254)))
+        );
+        assert_eq!(Int256::try_from(pow2(255)), Err(TryFromIntError));
+        assert_eq!(Uint256::try_from(int256(7)), Ok(small(7)));
+        assert_eq!(Uint256::try_from(int256(-7)), Err(TryFromIntError));
+        assert_eq!(Int256::MINUS_ONE.to_bits(), Uint256::MAX);
+        assert_eq!(Int256::from_bits(Uint256::MAX), Int256::MINUS_ONE);
+    }
+
+    #[test]
+    fn native_conversions() {
+        assert_eq!(Uint128::from(0x0102u16).bytes[..3], [2, 1, 0]);
+        assert_eq!(Uint256::from(u128::MAX), Uint256::from(Uint128::MAX));
+        assert_eq!(Uint256::from(7u8), small(7));
+        assert_eq!(u128::from(uint128(u128::MAX)), u128::MAX);
+
+        assert_eq!(u8::try_from(Uint128::from(255u8)), Ok(255));
+        assert_eq!(u8::try_from(Uint128::from(256u16)), Err(TryFromIntError));
+        assert_eq!(u64::try_from(Uint128::from(u64::MAX)), Ok(u64::MAX));
+        assert_eq!(u64::try_from(pow2(64)), Err(TryFromIntError));
+        assert_eq!(u128::try_from(pow2(127)), Ok(1 << 127));
+        assert_eq!(u128::try_from(pow2(128)), Err(TryFromIntError));
+        assert_eq!(Uint128::try_from(pow2(127)), Ok(uint128(1 << 127)));
+        assert_eq!(Uint128::try_from(pow2(128)), Err(TryFromIntError));
+    }
+
+    #[test]
+    fn endianness() {
+        let mut be = [0u8; 32];
+        be[31] = 1;
+        be[0] = 0x80;
+        let value = Uint256::from_be_bytes(be);
+        assert_eq!(value, pow2(255) + small(1));
+        assert_eq!(value.to_be_bytes(), be);
+        assert_eq!(Uint256::from_le_bytes(value.to_le_bytes()), value);
+        assert_eq!(
+            Uint128::from_be_bytes(0x1234u128.to_be_bytes()),
+            uint128(0x1234)
+        );
+        assert_eq!(uint128(0x1234).to_be_bytes(), 0x1234u128.to_be_bytes());
+        assert_eq!(Int256::from_be_bytes([0xff; 32]), Int256::MINUS_ONE);
+    }
+
+    #[test]
+    fn format_integers() {
+        assert_eq!(Uint128::MIN.to_string(), "0");
+        assert_eq!(Uint128::MAX.to_string(), u128::MAX.to_string());
+        assert_eq!(
+            Uint256::MAX.to_string(),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+        assert_eq!(format!("{:?}", uint128(1234)), "1234");
+        assert_eq!(format!("{:>6}", uint128(42)), "    42");
+        assert_eq!(format!("{:x}", pow2(255)), format!("8{}", "0".repeat(63)));
+        assert_eq!(format!("{:#x}", Uint256::MIN), "0x0");
+        assert_eq!(format!("{:#06X}", uint128(0xab)), "0x00AB");
+        assert_eq!(format!("{:x}", uint128(0x1234_5678)), "12345678");
+
+        assert_eq!(Int256::MINUS_ONE.to_string(), "-1");
+        assert_eq!(
+            Int256::MIN.to_string(),
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        );
+        assert_eq!(format!("{:x}", Int256::MINUS_ONE), "f".repeat(64));
+        assert_eq!(format!("{:?}", -int256(12)), "-12");
+    }
+
+    #[test]
+    fn parse_integers() {
+        assert_eq!("1234".parse::<Uint128>(), Ok(uint128(1234)));
+        assert_eq!("0x04d2".parse::<Uint128>(), Ok(uint128(1234)));
+        assert_eq!("0xABcd".parse::<Uint128>(), Ok(uint128(0xabcd)));
+        assert_eq!(u128::MAX.to_string().parse::<Uint128>(), Ok(Uint128::MAX));
+        assert_eq!(
+            "340282366920938463463374607431768211456".parse::<Uint128>(),
+            Err(ParseError::Overflow)
+        );
+        assert_eq!(
+            format!("0x1{}", "0".repeat(32)).parse::<Uint128>(),
+            Err(ParseError::Overflow)
+        );
+        assert_eq!(
+            format!("0x{}1", "0".repeat(64)).parse::<Uint128>(),
+            Ok(uint128(1))
+        );
+        assert_eq!(Uint256::MAX.to_string().parse(), Ok(Uint256::MAX));
+        assert_eq!("".parse::<Uint256>(), Err(ParseError::Empty));
+        assert_eq!("0x".parse::<Uint256>(), Err(ParseError::Empty));
+        assert_eq!("12a".parse::<Uint256>(), Err(ParseError::InvalidDigit));
+        assert_eq!("0xg".parse::<Uint256>(), Err(ParseError::InvalidDigit));
+
+        assert_eq!("-12".parse::<Int256>(), Ok(int256(-12)));
+        assert_eq!("+0x10".parse::<Int256>(), Ok(int256(16)));
+        assert_eq!(Int256::MIN.to_string().parse(), Ok(Int256::MIN));
+        assert_eq!(Int256::MAX.to_string().parse(), Ok(Int256::MAX));
+        assert_eq!(
+            format!("0x8{}", "0".repeat(63)).parse::<Int256>(),
+            Err(ParseError::Overflow)
+        );
+    }
+
+    #[test]
+    fn format_bytes() {
+        let mut address = Bytes20::default();
+        address.bytes[0] = 0xab;
+        address.bytes[19] = 0x01;
+        let text = format!("0xab{}01", "0".repeat(36));
+        assert_eq!(address.to_string(), text);
+        assert_eq!(format!("{:?}", address), text);
+        assert_eq!(format!("{:x}", address), text[2..]);
+        assert_eq!(
+            format!("{:#X}", address),
+            format!("0xAB{}01", "0".repeat(36))
+        );
+
+        assert_eq!(text.parse(), Ok(address));
+        assert_eq!(text[2..].parse(), Ok(address));
+        assert_eq!(text.to_uppercase()[2..].parse(), Ok(address));
+        assert_eq!(
+            text[..40].parse::<Bytes20>(),
+            Err(ParseError::InvalidLength)
+        );
+        assert_eq!(
+            format!("0x{}", "z".repeat(64)).parse::<Bytes32>(),
+            Err(ParseError::InvalidDigit)
+        );
+        assert_eq!(
+            format!("{}", Bytes32::from([0xff; 32])).parse(),
+            Ok(Bytes32::from([0xff; 32]))
+        );
+    }
+
+    #[test]
+    fn fixed_bytes() {
+        let selector: FixedBytes<4> = "0xa9059cbb".parse().unwrap();
+        assert_eq!(selector.bytes, [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(format!("{:?}", selector), "0xa9059cbb");
+        assert_eq!(format!("{:X}", selector), "A9059CBB");
+        assert_eq!(FixedBytes::<4>::LEN, 4);
+        assert_eq!(FixedBytes::<1>::default().to_string(), "0x00");
+
+        let hash = Bytes32::from([0x11; 32]);
+        assert_eq!(&hash[..4], &[0x11; 4]);
+        assert_eq!(hash[31], 0x11);
+        assert_eq!(hash.as_slice(), hash.as_ref());
+        assert_eq!(
+            FixedBytes::<4>::try_from(&hash[..4]).unwrap(),
+            FixedBytes::from([0x11; 4])
+        );
+        assert!(FixedBytes::<4>::try_from(&hash[..3]).is_err());
+
+        let mut value = FixedBytes::from([1u8, 2, 3]);
+        value[0] = 4;
+        value.as_mut()[1] = 5;
+        assert_eq!(<[u8; 3]>::from(value), [4, 5, 3]);
+        assert!(FixedBytes::from([1u8, 0xff]) < FixedBytes::from([2u8, 0]));
+        assert_eq!(
+            !FixedBytes::from([0x0fu8, 0]),
+            FixedBytes::from([0xf0, 0xff])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "primitive-types")]
+    fn primitive_types() {
+        use primitive_types::{H160, H256, U128, U256};
+
+        assert_eq!(U256::from(pow2(200)), U256::one() << 200);
+        assert_eq!(
+            Uint256::from(U256::from(0x0102u64)),
+            Uint256::from(0x0102u16)
+        );
+        assert_eq!(Uint256::from(U256::MAX), Uint256::MAX);
+        assert_eq!(
+            U128::from(uint128(u128::MAX - 1)),
+            U128::from(u128::MAX - 1)
+        );
+        assert_eq!(Uint128::from(U128::from(1234u64)), uint128(1234));
+        let value =
+            crate::uint256!("0x0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20");
+        assert_eq!(Uint256::from(U256::from(value)), value);
+        assert_eq!(U256::from(value).to_big_endian(), value.to_be_bytes());
+
+        let address = Bytes20::from([0xab; 20]);
+        assert_eq!(H160::from(address), H160::repeat_byte(0xab));
+        assert_eq!(Bytes20::from(H160([0xcd; 20])), Bytes20::from([0xcd; 20]));
+        let hash =
+            crate::bytes32!("0x0000000000000000000000000000000000000000000000000000000000000102");
+        let mut bytes = [0u8; 32];
+        bytes[30..].copy_from_slice(&[1, 2]);
+        assert_eq!(H256::from(hash), H256(bytes));
+        assert_eq!(Bytes32::from(H256::from(hash)), hash);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let json = serde_json::to_string(&(uint128(0), pow2(100), int256(-5))).unwrap();
+        assert_eq!(json, r#"["0x0","0x10000000000000000000000000","-5"]"#);
+        let (a, b, c): (Uint128, Uint256, Int256) = serde_json::from_str(&json).unwrap();
+        assert_eq!((a, b, c), (uint128(0), pow2(100), int256(-5)));
+        assert_eq!(
+            serde_json::from_str::<Uint256>(r#""1000""#).unwrap(),
+            small(100) * small(10)
+        );
+
+        let address = Bytes20::from([0xab; 20]);
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, format!(r#""0x{}""#, "ab".repeat(20)));
+        assert_eq!(serde_json::from_str::<Bytes20>(&json).unwrap(), address);
+        let selector: FixedBytes<4> = serde_json::from_str(r#""0xa9059cbb""#).unwrap();
+        assert_eq!(selector.bytes, [0xa9, 0x05, 0x9c, 0xbb]);
+
+        let error = serde_json::from_str::<Bytes32>(r#""0x12""#).unwrap_err();
+        assert!(error.to_string().starts_with("invalid number of digits"));
+        let error = serde_json::from_str::<Uint128>("12").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("a hexadecimal or decimal quantity string"));
+    }
+
+    #[test]
+    fn checksums() {
+        // The test vectors from EIP-55.
+        let addresses = [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0xde709f2102306220921060314715629080e2fb77",
+        ];
+        for text in addresses.iter() {
+            let address = Bytes20::from_checksum_str(text).unwrap();
+            assert_eq!(address.to_checksum_string(), *text);
+            assert_eq!(address.to_checksum_str(&mut [0; 42]), *text);
+            assert_eq!(address, text.parse().unwrap());
+            assert_eq!(Bytes20::from_checksum_str(&text[2..]), Ok(address));
+            assert_eq!(
+                Bytes20::from_checksum_str(&text.to_lowercase()),
+                Ok(address)
+            );
+            assert_eq!(
+                Bytes20::from_checksum_str(&text[2..].to_uppercase()),
+                Ok(address)
+            );
+        }
+
+        assert_eq!(
+            Bytes20::from_checksum_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"),
+            Err(ParseError::InvalidChecksum)
+        );
+        assert_eq!(
+            Bytes20::from_checksum_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA"),
+            Err(ParseError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn literals() {
+        const ADDRESS: Address = crate::address!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        assert_eq!(
+            ADDRESS,
+            "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".parse().unwrap()
+        );
+        assert_eq!(
+            crate::address!("0000000000000000000000000000000000000004"),
+            Address::from([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4])
+        );
+        assert_eq!(
+            crate::address!("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"),
+            crate::address!("FB6916095CA1DF60BB79CE92CE3EA74C37C5D359")
+        );
+
+        let slot =
+            crate::bytes32!("0x00000000000000000000000000000000000000000000000000000000000000FF");
+        assert_eq!(slot.bytes[31], 0xff);
+        assert_eq!(slot, Bytes32::from(Uint256::from(0xffu8).to_be_bytes()));
+
+        assert_eq!(crate::uint256!("0"), Uint256::MIN);
+        assert_eq!(crate::uint256!("0x0"), Uint256::MIN);
+        assert_eq!(
+            crate::uint256!(
+                "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+            ),
+            Uint256::MAX
+        );
+        assert_eq!(
+            crate::uint256!("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"),
+            Uint256::MAX
+        );
+        assert_eq!(
+            crate::uint256!("1000000000000000000"),
+            Uint256::from(10u64.pow(18))
+        );
+        assert_eq!(crate::uint256!("0x0100"), Uint256::from(256u16));
+
+        const KEY: StorageKey = crate::storage_key!(
+            "0x0000000000000000000000000000000000000000000000000000000000000002"
+        );
+        const TOPIC: LogTopic =
+            crate::log_topic!("0x0000000000000000000000000000000000000000000000000000000000000002");
+        assert_eq!(KEY.bytes[31], 2);
+        assert_eq!(KEY.bytes, TOPIC.bytes);
+        assert_eq!(
+            crate::ether_value!("0xffffffffffffffffffffffffffffffff"),
+            EtherValue::from(u128::MAX)
+        );
+        assert_eq!(
+            crate::ether_value!("1000000000"),
+            crate::ether::from_gwei(1)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn wei_literal_out_of_range() {
+        super::parse_wei_literal("0x100000000000000000000000000000000");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid EIP-55 checksum")]
+    fn literal_checksum_typo() {
+        super::parse_address_literal("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD");
+    }
+
+    #[test]
+    #[cfg(feature = "newtypes")]
+    fn newtypes() {
+        use super::{EtherValue, Hash, LogTopic, StorageKey, StorageValue};
+
+        let hash = Hash::from([7; 32]);
+        let key = StorageKey::from(hash);
+        assert_eq!(StorageValue::from(key), StorageValue::from([7; 32]));
+        assert_eq!(LogTopic::from(hash).to_string(), hash.to_string());
+        assert_eq!(Bytes32::from(key), Bytes32::from([7; 32]));
+        assert_eq!(
+            "0x0707070707070707070707070707070707070707070707070707070707070707".parse(),
+            Ok(key)
+        );
+
+        let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            address.to_checksum_string(),
+            Bytes20::from(address).to_checksum_string()
+        );
+
+        // The slice-like API of `FixedBytes` is forwarded.
+        assert_eq!(Address::LEN, 20);
+        assert_eq!(Hash::LEN, 32);
+        assert_eq!(address.as_slice(), Bytes20::from(address).as_slice());
+        assert_eq!(address.as_ref()[..2], [0x5a, 0xae]);
+        assert_eq!(address[19], 0xed);
+        assert_eq!(hash[..4], [7; 4]);
+        let mut topic = LogTopic::from(hash);
+        topic[0] = 8;
+        topic.as_mut()[1] = 9;
+        assert_eq!(topic[..3], [8, 9, 7]);
+        assert_eq!(Address::try_from(&address[..]).unwrap(), address);
+        assert!(StorageKey::try_from(&hash[1..]).is_err());
+
+        let value = EtherValue::from(100u8) * EtherValue::from(3u8);
+        assert_eq!(u128::from(value), 300);
+        assert_eq!(Uint128::from(value), uint128(300));
+        assert_eq!(EtherValue::try_from(Uint256::from(value)), Ok(value));
+        assert_eq!(EtherValue::MAX.checked_add(EtherValue::from(1u8)), None);
+        assert_eq!(format!("{} {:#x}", value, value), "300 0x12c");
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to add with overflow")]
+    fn uint256_add_overflow() {
+        let _ = Uint256::MAX + small(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to divide by zero")]
+    fn uint128_divide_by_zero() {
+        let _ = uint128(1) / Uint128::MIN;
+    }
 }
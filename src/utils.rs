@@ -2,7 +2,10 @@
 
 /// Allocate an owned buffer using the global allocator.
 /// Only enabled with `std`.
-#[cfg(feature = "std")]
+///
+/// On wasm32 the buffer is left uninitialized, as the VM overwrites all of it before it is read.
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+#[allow(clippy::uninit_vec)]
 pub fn unsafe_alloc_buffer(len: usize) -> Vec<u8> {
     let mut ret: Vec<u8> = Vec::with_capacity(len);
     unsafe {
@@ -11,6 +14,16 @@ pub fn unsafe_alloc_buffer(len: usize) -> Vec<u8> {
     ret
 }
 
+/// Allocate an owned buffer using the global allocator.
+/// Only enabled with `std`.
+///
+/// Elsewhere the EEI is implemented in Rust by the `testing` feature, so the buffer is zeroed
+/// rather than trusting the mock host to overwrite all of it.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub fn unsafe_alloc_buffer(len: usize) -> Vec<u8> {
+    vec![0; len]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,5 +32,6 @@ mod tests {
     fn smoke() {
         let ret = unsafe_alloc_buffer(42);
         assert_eq!(ret.len(), 42);
+        assert!(ret.iter().all(|byte| *byte == 0));
     }
 }